@@ -0,0 +1,250 @@
+use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
+
+// SPL token-swap `Swap` instruction tag
+pub const SWAP_INSTRUCTION_TAG: u8 = 1;
+
+// SPL token-swap `CurveType::ConstantProduct`
+pub const CURVE_TYPE_CONSTANT_PRODUCT: u8 = 0;
+
+/// Fields of an SPL token-swap (`SwapV1`) pool account used by the router
+#[derive(Clone, Debug)]
+pub struct TokenSwapPool {
+    pub bump_seed: u8,
+    pub token_program_id: Pubkey,
+    pub token_a: Pubkey,
+    pub token_b: Pubkey,
+    pub pool_mint: Pubkey,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub pool_fee_account: Pubkey,
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+    pub owner_trade_fee_numerator: u64,
+    pub owner_trade_fee_denominator: u64,
+    pub curve_type: u8,
+}
+
+impl TokenSwapPool {
+    // version (1) + is_initialized (1) + bump_seed (1) + 7 pubkeys (224) + fees (64) + curve (33)
+    pub const LEN: usize = 1 + 1 + 1 + 7 * 32 + 8 * 8 + 1 + 32;
+
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        require!(data.len() >= Self::LEN, ErrorCode::PoolNotFound);
+        // data[0] is the account version, data[1] is_initialized
+        require!(data[1] == 1, ErrorCode::PoolNotFound);
+
+        let pubkey_at = |offset: usize| -> Pubkey {
+            Pubkey::new_from_array(data[offset..offset + 32].try_into().unwrap())
+        };
+        let u64_at = |offset: usize| -> u64 {
+            u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+        };
+
+        Ok(Self {
+            bump_seed: data[2],
+            token_program_id: pubkey_at(3),
+            token_a: pubkey_at(35),
+            token_b: pubkey_at(67),
+            pool_mint: pubkey_at(99),
+            token_a_mint: pubkey_at(131),
+            token_b_mint: pubkey_at(163),
+            pool_fee_account: pubkey_at(195),
+            trade_fee_numerator: u64_at(227),
+            trade_fee_denominator: u64_at(235),
+            owner_trade_fee_numerator: u64_at(243),
+            owner_trade_fee_denominator: u64_at(251),
+            curve_type: data[291],
+        })
+    }
+
+    /// Resolve swap direction from the reserve accounts, returning (input mint, output mint)
+    pub fn direction(&self, reserve_in: &Pubkey, reserve_out: &Pubkey) -> Result<(Pubkey, Pubkey)> {
+        if *reserve_in == self.token_a && *reserve_out == self.token_b {
+            Ok((self.token_a_mint, self.token_b_mint))
+        } else if *reserve_in == self.token_b && *reserve_out == self.token_a {
+            Ok((self.token_b_mint, self.token_a_mint))
+        } else {
+            err!(ErrorCode::PoolMismatch)
+        }
+    }
+
+    /// Pool fees charged on `amount_in` (trade fee + owner trade fee)
+    pub fn trading_fees(&self, amount_in: u64) -> Result<u64> {
+        let trade_fee = pool_fee(amount_in, self.trade_fee_numerator, self.trade_fee_denominator)?;
+        let owner_fee = pool_fee(
+            amount_in,
+            self.owner_trade_fee_numerator,
+            self.owner_trade_fee_denominator,
+        )?;
+        trade_fee
+            .checked_add(owner_fee)
+            .ok_or(ErrorCode::MathOverflow.into())
+    }
+
+    /// Expected output of swapping `amount_in` against the given reserves,
+    /// matching the token-swap program's fee and rounding rules
+    pub fn swap_output(&self, amount_in: u64, reserve_in: u64, reserve_out: u64) -> Result<u64> {
+        let fees = self.trading_fees(amount_in)?;
+        let amount_in_less_fees = amount_in
+            .checked_sub(fees)
+            .ok_or(ErrorCode::InsufficientOutputAmount)?;
+        constant_product_output(amount_in_less_fees, reserve_in, reserve_out)
+    }
+}
+
+/// Token-swap fee: floor(amount * numerator / denominator), but at least 1 when charged
+fn pool_fee(amount: u64, numerator: u64, denominator: u64) -> Result<u64> {
+    if numerator == 0 || amount == 0 {
+        return Ok(0);
+    }
+    let fee = (amount as u128)
+        .checked_mul(numerator as u128)
+        .and_then(|result| result.checked_div(denominator as u128))
+        .ok_or(ErrorCode::MathOverflow)?;
+    let fee = u64::try_from(fee).map_err(|_| ErrorCode::MathOverflow)?;
+    Ok(fee.max(1))
+}
+
+/// Constant-product (x * y = k) output for a fee-free input, rounding in favour of the pool
+pub fn constant_product_output(amount_in: u64, reserve_in: u64, reserve_out: u64) -> Result<u64> {
+    require!(reserve_in > 0 && reserve_out > 0, ErrorCode::InsufficientLiquidity);
+
+    let invariant = (reserve_in as u128)
+        .checked_mul(reserve_out as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let new_reserve_in = (reserve_in as u128)
+        .checked_add(amount_in as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let new_reserve_out = invariant
+        .checked_add(new_reserve_in - 1)
+        .and_then(|result| result.checked_div(new_reserve_in))
+        .ok_or(ErrorCode::MathOverflow)?;
+    let amount_out = (reserve_out as u128)
+        .checked_sub(new_reserve_out)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    u64::try_from(amount_out).map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+/// Instruction data for token-swap `Swap { amount_in, minimum_amount_out }`
+pub fn swap_instruction_data(amount_in: u64, minimum_amount_out: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(17);
+    data.push(SWAP_INSTRUCTION_TAG);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&minimum_amount_out.to_le_bytes());
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool_data(keys: &[Pubkey; 8], fees: [u64; 4], curve_type: u8) -> Vec<u8> {
+        let mut data = vec![1, 1, 254]; // version, is_initialized, bump_seed
+        for key in keys.iter().take(7) {
+            data.extend_from_slice(key.as_ref());
+        }
+        for fee in fees {
+            data.extend_from_slice(&fee.to_le_bytes());
+        }
+        data.extend_from_slice(&[0; 32]); // owner withdraw and host fees
+        data.push(curve_type);
+        data.extend_from_slice(keys[7].as_ref()); // curve calculator
+        data
+    }
+
+    fn unique_keys() -> [Pubkey; 8] {
+        std::array::from_fn(|_| Pubkey::new_unique())
+    }
+
+    #[test]
+    fn unpacks_swap_v1_layout() {
+        let keys = unique_keys();
+        let data = pool_data(&keys, [25, 10_000, 5, 10_000], CURVE_TYPE_CONSTANT_PRODUCT);
+        assert_eq!(data.len(), TokenSwapPool::LEN);
+
+        let pool = TokenSwapPool::unpack(&data).unwrap();
+        assert_eq!(pool.bump_seed, 254);
+        assert_eq!(pool.token_program_id, keys[0]);
+        assert_eq!(pool.token_a, keys[1]);
+        assert_eq!(pool.token_b, keys[2]);
+        assert_eq!(pool.pool_mint, keys[3]);
+        assert_eq!(pool.token_a_mint, keys[4]);
+        assert_eq!(pool.token_b_mint, keys[5]);
+        assert_eq!(pool.pool_fee_account, keys[6]);
+        assert_eq!(pool.trade_fee_numerator, 25);
+        assert_eq!(pool.trade_fee_denominator, 10_000);
+        assert_eq!(pool.owner_trade_fee_numerator, 5);
+        assert_eq!(pool.owner_trade_fee_denominator, 10_000);
+        assert_eq!(pool.curve_type, CURVE_TYPE_CONSTANT_PRODUCT);
+    }
+
+    #[test]
+    fn rejects_short_or_uninitialized_pool() {
+        let data = pool_data(&unique_keys(), [0, 1, 0, 1], CURVE_TYPE_CONSTANT_PRODUCT);
+        assert_eq!(
+            TokenSwapPool::unpack(&data[..TokenSwapPool::LEN - 1]).unwrap_err(),
+            ErrorCode::PoolNotFound.into()
+        );
+
+        let mut data = data;
+        data[1] = 0;
+        assert_eq!(TokenSwapPool::unpack(&data).unwrap_err(), ErrorCode::PoolNotFound.into());
+    }
+
+    #[test]
+    fn resolves_direction_from_reserves() {
+        let keys = unique_keys();
+        let pool = TokenSwapPool::unpack(&pool_data(&keys, [0, 1, 0, 1], 0)).unwrap();
+
+        assert_eq!(pool.direction(&keys[1], &keys[2]).unwrap(), (keys[4], keys[5]));
+        assert_eq!(pool.direction(&keys[2], &keys[1]).unwrap(), (keys[5], keys[4]));
+        assert_eq!(
+            pool.direction(&keys[1], &keys[1]).unwrap_err(),
+            ErrorCode::PoolMismatch.into()
+        );
+    }
+
+    #[test]
+    fn constant_product_output_rounds_in_favour_of_pool() {
+        // k = 1_000_000; new reserve_out = ceil(1_000_000 / 1_100) = 910, so 90 out (not 91)
+        assert_eq!(constant_product_output(100, 1_000, 1_000).unwrap(), 90);
+        // Exact division leaves nothing to round
+        assert_eq!(constant_product_output(1_000, 1_000, 1_000).unwrap(), 500);
+        assert_eq!(constant_product_output(0, 1_000, 1_000).unwrap(), 0);
+        // Large reserves use u128 intermediates
+        assert_eq!(
+            constant_product_output(u64::MAX / 2, u64::MAX / 2, u64::MAX).unwrap(),
+            u64::MAX / 2
+        );
+        assert_eq!(
+            constant_product_output(1, 0, 1_000).unwrap_err(),
+            ErrorCode::InsufficientLiquidity.into()
+        );
+    }
+
+    #[test]
+    fn charges_at_least_one_unit_of_each_fee() {
+        let pool = TokenSwapPool::unpack(&pool_data(&unique_keys(), [25, 10_000, 5, 10_000], 0)).unwrap();
+        assert_eq!(pool.trading_fees(10_000).unwrap(), 25 + 5);
+        // 10 * 25 / 10_000 and 10 * 5 / 10_000 both floor to 0 and are bumped to 1
+        assert_eq!(pool.trading_fees(10).unwrap(), 2);
+        assert_eq!(pool.trading_fees(0).unwrap(), 0);
+
+        // swap_output quotes the fee-free input: 10_000 - 30 = 9_970 in
+        assert_eq!(
+            pool.swap_output(10_000, 1_000_000, 1_000_000).unwrap(),
+            constant_product_output(9_970, 1_000_000, 1_000_000).unwrap()
+        );
+    }
+
+    #[test]
+    fn encodes_swap_instruction() {
+        let data = swap_instruction_data(1_000, 990);
+        assert_eq!(data.len(), 17);
+        assert_eq!(data[0], SWAP_INSTRUCTION_TAG);
+        assert_eq!(u64::from_le_bytes(data[1..9].try_into().unwrap()), 1_000);
+        assert_eq!(u64::from_le_bytes(data[9..17].try_into().unwrap()), 990);
+    }
+}
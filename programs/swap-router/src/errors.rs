@@ -2,8 +2,21 @@ use anchor_lang::prelude::*;
 
 #[error_code]
 pub enum ErrorCode {
+    // The first four keep the codes they had when they were declared in router.rs
+    #[msg("Invalid Jupiter program provided")]
+    InvalidJupiterProgram = 6000,
+    
+    #[msg("Invalid fee rate - must be <= 1000 basis points")]
+    InvalidFeeRate,
+    
+    #[msg("Empty instruction data")]
+    EmptyInstructionData,
+    
+    #[msg("Unauthorized access")]
+    Unauthorized,
+    
     #[msg("Invalid DEX program provided")]
-    InvalidDexProgram = 6000,
+    InvalidDexProgram,
     
     #[msg("Swap failed during execution")]
     SwapFailed,
@@ -20,12 +33,6 @@ pub enum ErrorCode {
     #[msg("Slippage tolerance exceeded")]
     SlippageExceeded,
     
-    #[msg("Unauthorized access")]
-    Unauthorized,
-    
-    #[msg("Invalid fee rate - must be <= 1000 basis points")]
-    InvalidFeeRate,
-    
     #[msg("Math overflow occurred")]
     MathOverflow,
     
@@ -46,4 +53,76 @@ pub enum ErrorCode {
     
     #[msg("Token mint mismatch")]
     TokenMintMismatch,
+    
+    #[msg("Swap consumed more input than declared")]
+    SwapInputExceeded,
+    
+    #[msg("Vault authority is not a vault program PDA")]
+    InvalidVaultAuthority,
+    
+    #[msg("Router is paused")]
+    RouterPaused,
+    
+    #[msg("DEX registry is full")]
+    DexRegistryFull,
+    
+    #[msg("DEX program already registered")]
+    DexProgramAlreadyRegistered,
+    
+    #[msg("DEX program not registered")]
+    DexProgramNotRegistered,
+    
+    #[msg("Fee tiers must be sorted by ascending volume (max 8)")]
+    InvalidFeeSchedule,
+    
+    #[msg("Invalid referral share - must be <= 10000 basis points")]
+    InvalidReferralShare,
+    
+    #[msg("Referrer and referral fee account must be provided together")]
+    InvalidReferrer,
+    
+    #[msg("Insufficient fee balance")]
+    InsufficientFeeBalance,
+    
+    #[msg("Destination is not owned by the router treasury")]
+    InvalidTreasury,
+    
+    #[msg("Pool curve is not constant product")]
+    UnsupportedPoolCurve,
+    
+    #[msg("Mint has an unsupported Token-2022 extension")]
+    UnsupportedMintExtension,
+    
+    #[msg("Unknown Jupiter instruction discriminator")]
+    UnknownJupiterInstruction,
+    
+    #[msg("Malformed Jupiter route instruction data")]
+    InvalidJupiterRoute,
+    
+    #[msg("Jupiter route accounts do not match the swap token accounts")]
+    JupiterAccountMismatch,
+    
+    #[msg("Jupiter route amounts do not match the declared swap amounts")]
+    JupiterAmountMismatch,
+    
+    #[msg("Jupiter platform fees are not allowed")]
+    JupiterPlatformFeeNotAllowed,
+    
+    #[msg("Too many remaining accounts")]
+    TooManyAccounts,
+    
+    #[msg("Router or vault owned account cannot be passed as writable")]
+    ProtectedAccountWritable,
+    
+    #[msg("Only the user or vault authority may sign forwarded instructions")]
+    UnauthorizedSigner,
+    
+    #[msg("Router state is not at a migratable version")]
+    InvalidRouterStateVersion,
+    
+    #[msg("Stats window must be between 1 and 168 hours")]
+    InvalidStatsWindow,
+    
+    #[msg("Round-trip vault swaps require an exact-in Jupiter route for amount_in")]
+    RoundTripRequiresRoute,
 }
\ No newline at end of file
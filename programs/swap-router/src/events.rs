@@ -0,0 +1,110 @@
+use anchor_lang::prelude::*;
+use crate::router::FeeTier;
+
+#[event]
+pub struct SwapExecuted {
+    pub user: Pubkey,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    pub route_program: Pubkey,
+}
+
+#[event]
+pub struct VaultSwapExecuted {
+    pub vault: Pubkey,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub route_program: Pubkey,
+}
+
+#[event]
+pub struct RouterConfigChanged {
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+    pub fee_rate_bps: u16,
+    pub paused: bool,
+    pub treasury: Pubkey,
+    pub max_slot_volume: u64,
+    pub max_hourly_volume: u64,
+}
+
+#[event]
+pub struct DexRegistryChanged {
+    pub program_id: Pubkey,
+    pub registered: bool,
+    pub enabled: bool,
+}
+
+#[event]
+pub struct FeeScheduleChanged {
+    pub mint: Pubkey,
+    pub tiers: Vec<FeeTier>,
+}
+
+#[event]
+pub struct FeeOverrideChanged {
+    pub wallet: Pubkey,
+    pub fee_rate_bps: u16,
+    pub active: bool,
+}
+
+#[event]
+pub struct ReferrerRegistered {
+    pub referrer: Pubkey,
+    pub share_bps: u16,
+}
+
+#[event]
+pub struct ReferralFeeAccrued {
+    pub referrer: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub accrued: u64,
+}
+
+#[event]
+pub struct ReferralFeesClaimed {
+    pub referrer: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct FeesWithdrawn {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct RouteExecuted {
+    pub user: Pubkey,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    pub route_programs: Vec<Pubkey>,
+}
+
+#[event]
+pub struct RouterStateMigrated {
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+#[event]
+pub struct CircuitBreakerTripped {
+    pub slot: u64,
+    pub amount: u64,
+    pub slot_volume: u64,
+    pub hourly_volume: u64,
+    pub max_slot_volume: u64,
+    pub max_hourly_volume: u64,
+}
@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use crate::errors::ErrorCode;
+use crate::events::ReferralFeeAccrued;
+use crate::router::{FeeOverride, FeeSchedule, Referrer, UserSwapStats};
+use crate::utils::{calculate_fee, resolve_fee_rate, transfer_fee_amount};
+
+/// Accounts that price and collect the router fee, shared by every user swap instruction
+pub struct RouterFeeAccounts<'a, 'info> {
+    pub user: &'a Signer<'info>,
+    pub user_source_token: &'a InterfaceAccount<'info, TokenAccount>,
+    pub input_mint: &'a InterfaceAccount<'info, Mint>,
+    pub fee_vault: &'a InterfaceAccount<'info, TokenAccount>,
+    pub fee_override: Option<&'a Account<'info, FeeOverride>>,
+    pub fee_schedule: Option<&'a Account<'info, FeeSchedule>>,
+    pub user_stats: Option<&'a Account<'info, UserSwapStats>>,
+    pub referrer: Option<&'a Account<'info, Referrer>>,
+    pub referral_fee_vault: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: &'a Interface<'info, TokenInterface>,
+}
+
+impl<'info> RouterFeeAccounts<'_, 'info> {
+    /// Fee rate for this user and input mint: wallet override, then volume tier, then base rate
+    pub fn fee_rate(&self, base_fee_rate_bps: u16, now: i64) -> u16 {
+        resolve_fee_rate(
+            base_fee_rate_bps,
+            self.fee_override.map(|fee_override| &**fee_override),
+            self.fee_schedule.map(|fee_schedule| &**fee_schedule),
+            self.user_stats.map(|user_stats| &**user_stats),
+            &self.input_mint.key(),
+            now,
+        )
+    }
+
+    /// Move `fee` from the user's source account, splitting it between the
+    /// protocol fee vault and the referrer's fee account when a referrer is given.
+    /// Returns the fee actually received, net of Token-2022 transfer fees.
+    pub fn collect(&self, fee: u64) -> Result<u64> {
+        let referral_fee = match self.referrer {
+            Some(referrer) => calculate_fee(fee, referrer.share_bps)?,
+            None => 0,
+        };
+        let protocol_fee = fee.checked_sub(referral_fee).ok_or(ErrorCode::MathOverflow)?;
+
+        let mut fee_received = 0;
+        if protocol_fee > 0 {
+            fee_received = self.transfer(self.fee_vault, protocol_fee)?;
+        }
+
+        if referral_fee > 0 {
+            let referrer = self.referrer.ok_or(ErrorCode::InvalidReferrer)?;
+            // The vault's address is checked against the referrer and mint by the account constraints
+            let referral_fee_vault = self.referral_fee_vault.ok_or(ErrorCode::InvalidReferrer)?;
+
+            let referral_fee_received = self.transfer(referral_fee_vault, referral_fee)?;
+            fee_received = fee_received.checked_add(referral_fee_received)
+                .ok_or(ErrorCode::MathOverflow)?;
+
+            emit!(ReferralFeeAccrued {
+                referrer: referrer.wallet,
+                mint: self.input_mint.key(),
+                amount: referral_fee_received,
+                accrued: referral_fee_vault.amount.checked_add(referral_fee_received)
+                    .ok_or(ErrorCode::MathOverflow)?,
+            });
+        }
+
+        Ok(fee_received)
+    }
+
+    /// Transfer `amount` to `to`, returning what arrives after any Token-2022 transfer fee
+    fn transfer(&self, to: &InterfaceAccount<'info, TokenAccount>, amount: u64) -> Result<u64> {
+        token_interface::transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.user_source_token.to_account_info(),
+                    mint: self.input_mint.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: self.user.to_account_info(),
+                },
+            ),
+            amount,
+            self.input_mint.decimals,
+        )?;
+
+        amount
+            .checked_sub(transfer_fee_amount(&self.input_mint.to_account_info(), amount)?)
+            .ok_or(ErrorCode::MathOverflow.into())
+    }
+}
@@ -0,0 +1,344 @@
+use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
+use crate::router::JUPITER_V6;
+
+// Anchor discriminators of the Jupiter V6 swap instructions
+pub const ROUTE_DISCRIMINATOR: [u8; 8] = [229, 23, 203, 151, 122, 227, 173, 42];
+pub const SHARED_ACCOUNTS_ROUTE_DISCRIMINATOR: [u8; 8] = [193, 32, 155, 51, 65, 214, 156, 129];
+pub const EXACT_OUT_ROUTE_DISCRIMINATOR: [u8; 8] = [208, 51, 239, 151, 123, 43, 237, 92];
+
+// Fixed-size tail shared by all route instructions:
+// two amounts (16) + slippage_bps (2) + platform_fee_bps (1)
+const ROUTE_ARGS_TAIL_LEN: usize = 8 + 8 + 2 + 1;
+
+/// Jupiter V6 swap instruction variants understood by the router
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JupiterRouteKind {
+    Route,
+    SharedAccountsRoute,
+    ExactOutRoute,
+}
+
+impl JupiterRouteKind {
+    pub fn from_discriminator(discriminator: &[u8]) -> Result<Self> {
+        match discriminator {
+            d if d == ROUTE_DISCRIMINATOR => Ok(Self::Route),
+            d if d == SHARED_ACCOUNTS_ROUTE_DISCRIMINATOR => Ok(Self::SharedAccountsRoute),
+            d if d == EXACT_OUT_ROUTE_DISCRIMINATOR => Ok(Self::ExactOutRoute),
+            _ => err!(ErrorCode::UnknownJupiterInstruction),
+        }
+    }
+
+    /// Position of the user's source token account in the instruction accounts
+    fn source_index(&self) -> usize {
+        match self {
+            Self::Route | Self::ExactOutRoute => 2,
+            Self::SharedAccountsRoute => 3,
+        }
+    }
+
+    /// Position of the account receiving the output, plus an optional override
+    /// slot that Jupiter uses instead when it is not set to the Jupiter program ID
+    fn destination_indexes(&self) -> (usize, Option<usize>) {
+        match self {
+            Self::Route | Self::ExactOutRoute => (3, Some(4)),
+            Self::SharedAccountsRoute => (6, None),
+        }
+    }
+}
+
+/// Amounts and fees decoded from a Jupiter V6 route instruction
+#[derive(Clone, Debug)]
+pub struct JupiterRoute {
+    pub kind: JupiterRouteKind,
+    /// Exact input for `route` / `shared_accounts_route`, quoted input for `exact_out_route`
+    pub in_amount: u64,
+    /// Quoted output for `route` / `shared_accounts_route`, exact output for `exact_out_route`
+    pub out_amount: u64,
+    pub slippage_bps: u16,
+    pub platform_fee_bps: u8,
+}
+
+impl JupiterRoute {
+    /// Decode the instruction data. The route plan is variable-length, so the
+    /// amounts are read from the fixed-size argument tail that follows it.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        require!(data.len() >= 8, ErrorCode::EmptyInstructionData);
+        let kind = JupiterRouteKind::from_discriminator(&data[..8])?;
+
+        // discriminator (8) + [id (1)] + route plan length prefix (4) + tail
+        let min_len = match kind {
+            JupiterRouteKind::SharedAccountsRoute => 8 + 1 + 4 + ROUTE_ARGS_TAIL_LEN,
+            _ => 8 + 4 + ROUTE_ARGS_TAIL_LEN,
+        };
+        require!(data.len() >= min_len, ErrorCode::InvalidJupiterRoute);
+
+        let tail = &data[data.len() - ROUTE_ARGS_TAIL_LEN..];
+        let first = u64::from_le_bytes(tail[0..8].try_into().unwrap());
+        let second = u64::from_le_bytes(tail[8..16].try_into().unwrap());
+        let slippage_bps = u16::from_le_bytes(tail[16..18].try_into().unwrap());
+        let platform_fee_bps = tail[18];
+
+        require!(slippage_bps <= 10000, ErrorCode::InvalidJupiterRoute);
+
+        let (in_amount, out_amount) = match kind {
+            JupiterRouteKind::ExactOutRoute => (second, first),
+            _ => (first, second),
+        };
+
+        Ok(Self {
+            kind,
+            in_amount,
+            out_amount,
+            slippage_bps,
+            platform_fee_bps,
+        })
+    }
+
+    /// Most the route may spend, including slippage for `exact_out_route`
+    pub fn max_in_amount(&self) -> Result<u64> {
+        match self.kind {
+            JupiterRouteKind::ExactOutRoute => apply_bps(self.in_amount, 10000 + self.slippage_bps as u64),
+            _ => Ok(self.in_amount),
+        }
+    }
+
+    /// Check the route spends from `source` and delivers to `destination`
+    pub fn validate_accounts(
+        &self,
+        accounts: &[AccountInfo],
+        source: &Pubkey,
+        destination: &Pubkey,
+    ) -> Result<()> {
+        let key_at = |index: usize| -> Result<Pubkey> {
+            accounts
+                .get(index)
+                .map(|account| account.key())
+                .ok_or(ErrorCode::JupiterAccountMismatch.into())
+        };
+
+        require_keys_eq!(key_at(self.kind.source_index())?, *source, ErrorCode::JupiterAccountMismatch);
+
+        let (index, override_index) = self.kind.destination_indexes();
+        let mut output_account = key_at(index)?;
+        if let Some(override_index) = override_index {
+            let override_account = key_at(override_index)?;
+            if override_account != JUPITER_V6 {
+                output_account = override_account;
+            }
+        }
+        require_keys_eq!(output_account, *destination, ErrorCode::JupiterAccountMismatch);
+
+        Ok(())
+    }
+
+    /// Cross-check the decoded amounts against the caller's declared bounds
+    pub fn validate_amounts(&self, max_in: u64, min_out: u64) -> Result<()> {
+        // The router charges its own fee; a Jupiter platform fee would divert output elsewhere
+        require!(self.platform_fee_bps == 0, ErrorCode::JupiterPlatformFeeNotAllowed);
+        require!(self.max_in_amount()? <= max_in, ErrorCode::JupiterAmountMismatch);
+        require!(self.out_amount >= min_out, ErrorCode::JupiterAmountMismatch);
+        Ok(())
+    }
+}
+
+/// floor(amount * bps / 10000)
+fn apply_bps(amount: u64, bps: u64) -> Result<u64> {
+    let result = (amount as u128)
+        .checked_mul(bps as u128)
+        .and_then(|result| result.checked_div(10000))
+        .ok_or(ErrorCode::MathOverflow)?;
+    u64::try_from(result).map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Route instruction data with an empty route plan
+    fn route_data(
+        discriminator: [u8; 8],
+        first: u64,
+        second: u64,
+        slippage_bps: u16,
+        platform_fee_bps: u8,
+    ) -> Vec<u8> {
+        let mut data = discriminator.to_vec();
+        if discriminator == SHARED_ACCOUNTS_ROUTE_DISCRIMINATOR {
+            data.push(0); // id
+        }
+        data.extend_from_slice(&0u32.to_le_bytes()); // route plan length
+        data.extend_from_slice(&first.to_le_bytes());
+        data.extend_from_slice(&second.to_le_bytes());
+        data.extend_from_slice(&slippage_bps.to_le_bytes());
+        data.push(platform_fee_bps);
+        data
+    }
+
+    #[test]
+    fn parses_route() {
+        let route = JupiterRoute::parse(&route_data(ROUTE_DISCRIMINATOR, 1_000, 990, 50, 0)).unwrap();
+        assert_eq!(route.kind, JupiterRouteKind::Route);
+        assert_eq!(route.in_amount, 1_000);
+        assert_eq!(route.out_amount, 990);
+        assert_eq!(route.slippage_bps, 50);
+        assert_eq!(route.max_in_amount().unwrap(), 1_000);
+    }
+
+    #[test]
+    fn parses_shared_accounts_route() {
+        let data = route_data(SHARED_ACCOUNTS_ROUTE_DISCRIMINATOR, 1_000, 990, 50, 0);
+        assert_eq!(data.len(), 8 + 1 + 4 + ROUTE_ARGS_TAIL_LEN);
+
+        let route = JupiterRoute::parse(&data).unwrap();
+        assert_eq!(route.kind, JupiterRouteKind::SharedAccountsRoute);
+        assert_eq!(route.in_amount, 1_000);
+        assert_eq!(route.out_amount, 990);
+    }
+
+    #[test]
+    fn parses_exact_out_route() {
+        // exact_out_route encodes out_amount before quoted_in_amount
+        let route = JupiterRoute::parse(&route_data(EXACT_OUT_ROUTE_DISCRIMINATOR, 500, 1_000, 100, 0)).unwrap();
+        assert_eq!(route.kind, JupiterRouteKind::ExactOutRoute);
+        assert_eq!(route.in_amount, 1_000);
+        assert_eq!(route.out_amount, 500);
+        assert_eq!(route.max_in_amount().unwrap(), 1_010);
+    }
+
+    #[test]
+    fn reads_amounts_after_route_plan() {
+        let mut data = ROUTE_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&[0xAA; 10]); // opaque route plan steps
+        data.extend_from_slice(&7u64.to_le_bytes());
+        data.extend_from_slice(&6u64.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.push(0);
+
+        let route = JupiterRoute::parse(&data).unwrap();
+        assert_eq!((route.in_amount, route.out_amount), (7, 6));
+    }
+
+    #[test]
+    fn rejects_short_data() {
+        assert_eq!(
+            JupiterRoute::parse(&ROUTE_DISCRIMINATOR[..7]).unwrap_err(),
+            ErrorCode::EmptyInstructionData.into()
+        );
+
+        let data = route_data(ROUTE_DISCRIMINATOR, 1, 1, 0, 0);
+        assert_eq!(
+            JupiterRoute::parse(&data[..data.len() - 1]).unwrap_err(),
+            ErrorCode::InvalidJupiterRoute.into()
+        );
+
+        // Long enough for `route`, one byte short for `shared_accounts_route`
+        let mut data = route_data(ROUTE_DISCRIMINATOR, 1, 1, 0, 0);
+        data[..8].copy_from_slice(&SHARED_ACCOUNTS_ROUTE_DISCRIMINATOR);
+        assert_eq!(
+            JupiterRoute::parse(&data).unwrap_err(),
+            ErrorCode::InvalidJupiterRoute.into()
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_discriminator() {
+        let data = route_data([1, 2, 3, 4, 5, 6, 7, 8], 1_000, 990, 50, 0);
+        assert_eq!(
+            JupiterRoute::parse(&data).unwrap_err(),
+            ErrorCode::UnknownJupiterInstruction.into()
+        );
+    }
+
+    #[test]
+    fn rejects_slippage_over_100_percent() {
+        assert!(JupiterRoute::parse(&route_data(ROUTE_DISCRIMINATOR, 1_000, 990, 10_000, 0)).is_ok());
+        assert_eq!(
+            JupiterRoute::parse(&route_data(ROUTE_DISCRIMINATOR, 1_000, 990, 10_001, 0)).unwrap_err(),
+            ErrorCode::InvalidJupiterRoute.into()
+        );
+    }
+
+    #[test]
+    fn validates_amounts() {
+        let route = JupiterRoute::parse(&route_data(ROUTE_DISCRIMINATOR, 1_000, 990, 50, 0)).unwrap();
+        assert!(route.validate_amounts(1_000, 990).is_ok());
+        assert_eq!(route.validate_amounts(999, 0).unwrap_err(), ErrorCode::JupiterAmountMismatch.into());
+        assert_eq!(route.validate_amounts(1_000, 991).unwrap_err(), ErrorCode::JupiterAmountMismatch.into());
+
+        // Slippage on an exact-out route counts against the input bound
+        let route = JupiterRoute::parse(&route_data(EXACT_OUT_ROUTE_DISCRIMINATOR, 500, 1_000, 100, 0)).unwrap();
+        assert!(route.validate_amounts(1_010, 500).is_ok());
+        assert_eq!(route.validate_amounts(1_009, 500).unwrap_err(), ErrorCode::JupiterAmountMismatch.into());
+
+        let route = JupiterRoute::parse(&route_data(ROUTE_DISCRIMINATOR, 1_000, 990, 50, 1)).unwrap();
+        assert_eq!(
+            route.validate_amounts(1_000, 0).unwrap_err(),
+            ErrorCode::JupiterPlatformFeeNotAllowed.into()
+        );
+    }
+
+    /// Validate `route` against accounts with the given keys
+    fn validate_keys(route: &JupiterRoute, keys: &[Pubkey], source: &Pubkey, destination: &Pubkey) -> Result<()> {
+        let owner = Pubkey::default();
+        let mut lamports = vec![0u64; keys.len()];
+        let mut data = vec![Vec::<u8>::new(); keys.len()];
+        let accounts: Vec<AccountInfo> = keys
+            .iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .map(|((key, lamports), data)| {
+                AccountInfo::new(key, false, false, lamports, data, &owner, false, 0)
+            })
+            .collect();
+        route.validate_accounts(&accounts, source, destination)
+    }
+
+    #[test]
+    fn uses_destination_override_slot() {
+        let route = JupiterRoute::parse(&route_data(ROUTE_DISCRIMINATOR, 1_000, 990, 50, 0)).unwrap();
+        let source = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let filler = Pubkey::new_unique();
+
+        // Override slot set to the Jupiter program: output goes to the destination token account
+        let keys = [filler, filler, source, destination, JUPITER_V6];
+        assert!(validate_keys(&route, &keys, &source, &destination).is_ok());
+
+        // Any other override redirects the output, even if index 3 is the destination
+        let keys = [filler, filler, source, destination, other];
+        assert_eq!(
+            validate_keys(&route, &keys, &source, &destination).unwrap_err(),
+            ErrorCode::JupiterAccountMismatch.into()
+        );
+        assert!(validate_keys(&route, &keys, &source, &other).is_ok());
+
+        // Missing override slot
+        let keys = [filler, filler, source, destination];
+        assert_eq!(
+            validate_keys(&route, &keys, &source, &destination).unwrap_err(),
+            ErrorCode::JupiterAccountMismatch.into()
+        );
+
+        // Wrong source
+        let keys = [filler, filler, other, destination, JUPITER_V6];
+        assert_eq!(
+            validate_keys(&route, &keys, &source, &destination).unwrap_err(),
+            ErrorCode::JupiterAccountMismatch.into()
+        );
+    }
+
+    #[test]
+    fn shared_accounts_route_has_no_override_slot() {
+        let data = route_data(SHARED_ACCOUNTS_ROUTE_DISCRIMINATOR, 1_000, 990, 50, 0);
+        let route = JupiterRoute::parse(&data).unwrap();
+        let source = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let filler = Pubkey::new_unique();
+
+        let keys = [filler, filler, filler, source, filler, filler, destination];
+        assert!(validate_keys(&route, &keys, &source, &destination).is_ok());
+    }
+}
@@ -1,7 +1,15 @@
 pub mod router;
+pub mod constant_product;
+pub mod jupiter;
+pub mod fees;
 pub mod utils;
 pub mod errors;
+pub mod events;
 
 pub use router::*;
+pub use constant_product::*;
+pub use jupiter::*;
+pub use fees::*;
 pub use utils::*;
-pub use errors::*;
\ No newline at end of file
+pub use errors::*;
+pub use events::*;
\ No newline at end of file
@@ -1,16 +1,28 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::{
     instruction::{AccountMeta, Instruction},
-    program::invoke_signed,
+    program::{invoke, invoke_signed},
+};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use anchor_lang::system_program::{self, System, Transfer};
+use crate::constant_product::{swap_instruction_data, TokenSwapPool, CURVE_TYPE_CONSTANT_PRODUCT};
+use crate::errors::ErrorCode;
+use crate::events::*;
+use crate::fees::RouterFeeAccounts;
+use crate::jupiter::{JupiterRoute, JupiterRouteKind};
+use crate::utils::{
+    calculate_fee, calculate_net_amount, calculate_price_impact, read_token_account,
+    sanitize_remaining_accounts, validate_dex_program, validate_mint_extensions,
 };
-use anchor_spl::token::Token;
-use anchor_lang::system_program::System;
 
 declare_id!("EoUeQknw3Mt1jbpHT6KCADu9YmD5ZgT1JFZSTDV8mNdP");
 
-// Jupiter V6 Program ID (Devnet & Mainnet)
+// Jupiter V6 Program ID (Devnet & Mainnet) - seeded into the DEX registry
 pub const JUPITER_V6: Pubkey = pubkey!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
 
+// AtomX Vault Program ID - the only program allowed to route vault swaps
+pub const VAULT_PROGRAM: Pubkey = pubkey!("J9L1xWf6Krkg7284UThzykxNZ133Sw7Kk2fLHJ2cpKSn");
+
 /// Swap Router Program
 #[program]
 pub mod swap_router {
@@ -29,46 +41,154 @@ pub mod swap_router {
         router.total_swaps = 0;
         router.total_volume = 0;
         router.bump = ctx.bumps.router_state;
+        router.pending_authority = Pubkey::default();
+        router.paused = false;
+        router.treasury = Pubkey::default();
+        router.version = RouterState::CURRENT_VERSION;
+        router.max_slot_volume = 0;
+        router.max_hourly_volume = 0;
+        router.current_slot = 0;
+        router.slot_volume = 0;
+        router.reserved = [0; RouterState::RESERVED_LEN];
+        
+        emit!(router.config_changed());
         
         msg!(" Router initialized with fee: {} bps", fee_rate_bps);
         Ok(())
     }
 
+    /// Upgrade a router state account written by an older program version
+    ///
+    /// v0 accounts (authority, fee_rate_bps, total_swaps, total_volume, bump) are
+    /// reallocated to the current size in place, keeping their fields; fields added
+    /// since start at their defaults (no pending authority, unpaused, no treasury).
+    /// The migration is deterministic, so any payer may fund the extra rent.
+    pub fn migrate_router_state(ctx: Context<MigrateRouterState>) -> Result<()> {
+        let router_info = ctx.accounts.router_state.to_account_info();
+        let router = RouterState::from_v0(&router_info.try_borrow_data()?)?;
+        
+        let new_len = 8 + RouterState::INIT_SPACE;
+        let rent_due = Rent::get()?
+            .minimum_balance(new_len)
+            .saturating_sub(router_info.lamports());
+        if rent_due > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: router_info.clone(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+        
+        router_info.resize(new_len)?;
+        router.try_serialize(&mut &mut router_info.try_borrow_mut_data()?[..])?;
+        
+        emit!(RouterStateMigrated {
+            from_version: 0,
+            to_version: RouterState::CURRENT_VERSION,
+        });
+        
+        msg!(" Router state migrated: v0 -> v{}", RouterState::CURRENT_VERSION);
+        Ok(())
+    }
+
     /// Execute a swap via Jupiter aggregator
     /// All accounts and instruction data come from Jupiter API
+    ///
+    /// The router fee is charged on the input side: `fee_rate_bps` of `amount_in`
+    /// is moved into the router fee vault for the input mint before the CPI, so the
+    /// Jupiter route must be quoted for `amount_in - fee`.
+    ///
+    /// The fee rate resolves to the user's `FeeOverride` if present, otherwise the
+    /// input mint's `FeeSchedule` tier for the user's 30-day volume, otherwise
+    /// `router_state.fee_rate_bps`.
+    ///
+    /// The output received in `user_destination_token` is measured after the CPI
+    /// and must be at least `min_amount_out`, independent of the route payload.
+    ///
+    /// If the measured input pushes the slot or hourly volume over a circuit breaker
+    /// cap, the swap completes and the router pauses (`CircuitBreakerTripped`).
+    ///
+    /// Jupiter V6 payloads are decoded first: the route must spend from
+    /// `user_source_token`, deliver to `user_destination_token`, stay within the
+    /// post-fee swap amount, quote at least `min_amount_out` and carry no platform fee.
     pub fn execute_jupiter_swap(
         ctx: Context<ExecuteJupiterSwap>,
         jupiter_instruction_data: Vec<u8>,
+        amount_in: u64,
+        min_amount_out: u64,
     ) -> Result<()> {
         msg!(" Executing Jupiter swap");
         
-        // Validate Jupiter program
-        require!(
-            ctx.accounts.jupiter_program.key() == JUPITER_V6,
-            ErrorCode::InvalidJupiterProgram
-        );
+        require!(amount_in > 0, ErrorCode::InvalidSwapAmount);
+        
+        // Validate target program against the DEX registry
+        validate_dex_program(&ctx.accounts.dex_registry, &ctx.accounts.jupiter_program.key())?;
         
         require!(
             !jupiter_instruction_data.is_empty(),
             ErrorCode::EmptyInstructionData
         );
         
-        // Update router stats
-        let router = &mut ctx.accounts.router_state;
-        router.total_swaps = router.total_swaps.checked_add(1).unwrap();
+        require!(
+            ctx.accounts.user_source_token.mint != ctx.accounts.user_destination_token.mint,
+            ErrorCode::InvalidTokenPair
+        );
+        
+        validate_mint_extensions(&ctx.accounts.input_mint.to_account_info())?;
+        validate_mint_extensions(&ctx.accounts.output_mint.to_account_info())?;
+        
+        let clock = Clock::get()?;
+        
+        // Collect router fee from the input amount
+        let fee_rate_bps = ctx.accounts
+            .fee_accounts()
+            .fee_rate(ctx.accounts.router_state.fee_rate_bps, clock.unix_timestamp);
+        let fee = calculate_fee(amount_in, fee_rate_bps)?;
+        let swap_amount = calculate_net_amount(amount_in, fee_rate_bps)?;
+        
+        // Cross-check the Jupiter payload against the typed accounts and declared amounts
+        if ctx.accounts.jupiter_program.key() == JUPITER_V6 {
+            let route = JupiterRoute::parse(&jupiter_instruction_data)?;
+            route.validate_accounts(
+                ctx.remaining_accounts,
+                &ctx.accounts.user_source_token.key(),
+                &ctx.accounts.user_destination_token.key(),
+            )?;
+            route.validate_amounts(swap_amount, min_amount_out)?;
+            
+            msg!(
+                " Jupiter route: in {}, out {}, slippage {} bps",
+                route.in_amount,
+                route.out_amount,
+                route.slippage_bps
+            );
+        }
+        
+        // Split the fee between protocol and referrer
+        let fee_received = ctx.accounts.fee_accounts().collect(fee)?;
+        
+        if fee > 0 {
+            ctx.accounts.user_source_token.reload()?;
+        }
+        
+        // Snapshot balances around the CPI
+        let source_before = ctx.accounts.user_source_token.amount;
+        let destination_before = ctx.accounts.user_destination_token.amount;
         
         // Build Jupiter instruction
         // ALL accounts come from remaining_accounts
         let jupiter_ix = Instruction {
-            program_id: JUPITER_V6,
-            accounts: ctx.remaining_accounts
-                .iter()
-                .map(|acc| AccountMeta {
-                    pubkey: acc.key(),
-                    is_signer: acc.is_signer,
-                    is_writable: acc.is_writable,
-                })
-                .collect(),
+            program_id: ctx.accounts.jupiter_program.key(),
+            accounts: sanitize_remaining_accounts(
+                ctx.remaining_accounts,
+                &ctx.accounts.user.key(),
+                &ctx.accounts.router_state.key(),
+            )?,
             data: jupiter_instruction_data,
         };
         
@@ -79,164 +199,2351 @@ pub mod swap_router {
             &[], // No PDA signing needed for basic swaps
         )?;
         
-        msg!(" Jupiter swap completed. Total swaps: {}", router.total_swaps);
+        ctx.accounts.user_source_token.reload()?;
+        ctx.accounts.user_destination_token.reload()?;
+        
+        let amount_swapped = source_before
+            .checked_sub(ctx.accounts.user_source_token.amount)
+            .ok_or(ErrorCode::SwapFailed)?;
+        let amount_out = ctx.accounts.user_destination_token.amount
+            .checked_sub(destination_before)
+            .ok_or(ErrorCode::SwapFailed)?;
+        
+        // The fee was charged on amount_in, so the route may not spend more than the net amount
+        require!(amount_swapped <= swap_amount, ErrorCode::SwapInputExceeded);
+        
+        // Protocol-level minimum output guarantee
+        require!(amount_out >= min_amount_out, ErrorCode::SlippageExceeded);
+        
+        // Update router stats
+        let router = &mut ctx.accounts.router_state;
+        router.record_swap(&mut ctx.accounts.router_metrics, &clock, amount_swapped, fee_received)?;
+        
+        let mint_stats = &mut ctx.accounts.mint_stats;
+        mint_stats.mint = ctx.accounts.input_mint.key();
+        mint_stats.total_volume = mint_stats.total_volume.checked_add(amount_swapped)
+            .ok_or(ErrorCode::MathOverflow)?;
+        mint_stats.bump = ctx.bumps.mint_stats;
+        
+        if let Some(user_stats) = ctx.accounts.user_stats.as_mut() {
+            user_stats.record_swap(
+                ctx.accounts.input_mint.key(),
+                amount_swapped,
+                fee_received,
+                clock.slot,
+                clock.unix_timestamp,
+            )?;
+        }
+        
+        msg!(
+            " Jupiter swap completed. In: {}, Out: {}, Fee: {}, Total swaps: {}",
+            amount_swapped,
+            amount_out,
+            fee_received,
+            router.total_swaps
+        );
+        
+        emit!(SwapExecuted {
+            user: ctx.accounts.user.key(),
+            input_mint: ctx.accounts.input_mint.key(),
+            output_mint: ctx.accounts.user_destination_token.mint,
+            amount_in: amount_swapped,
+            amount_out,
+            fee: fee_received,
+            route_program: ctx.accounts.jupiter_program.key(),
+        });
+        
         Ok(())
     }
 
     /// Execute swap using vault authority (for vault-owned funds)
+    ///
+    /// Source and destination may be the same account for round-trip arbitrage;
+    /// in that case the spent amount cannot be observed, so the call must carry an
+    /// exact-in Jupiter route for `amount_in` and the route's input is recorded as volume.
+    ///
+    /// The vault authority must arrive as a signer of the vault program's CPI;
+    /// its signature is forwarded to Jupiter, so no seeds are taken from the caller.
+    ///
+    /// Jupiter V6 payloads must route between the vault token accounts, spend at
+    /// most `amount_in` and carry no platform fee.
+    ///
+    /// Like user swaps, the circuit breaker trips on the measured volume after the
+    /// swap. The pause only persists if the vault's arbitrage then succeeds.
     pub fn execute_vault_jupiter_swap(
         ctx: Context<ExecuteVaultJupiterSwap>,
         jupiter_instruction_data: Vec<u8>,
-        vault_seeds: Vec<Vec<u8>>,
+        amount_in: u64,
     ) -> Result<()> {
         msg!(" Executing Jupiter swap with vault authority");
         
-        require!(
-            ctx.accounts.jupiter_program.key() == JUPITER_V6,
-            ErrorCode::InvalidJupiterProgram
-        );
+        require!(amount_in > 0, ErrorCode::InvalidSwapAmount);
+        
+        validate_dex_program(&ctx.accounts.dex_registry, &ctx.accounts.jupiter_program.key())?;
+        validate_mint_extensions(&ctx.accounts.output_mint.to_account_info())?;
+        
+        let route = if ctx.accounts.jupiter_program.key() == JUPITER_V6 {
+            let route = JupiterRoute::parse(&jupiter_instruction_data)?;
+            route.validate_accounts(
+                ctx.remaining_accounts,
+                &ctx.accounts.source_token.key(),
+                &ctx.accounts.destination_token.key(),
+            )?;
+            route.validate_amounts(amount_in, 0)?;
+            Some(route)
+        } else {
+            None
+        };
+        
+        // A round trip nets the spend against the output, so its volume is the
+        // exact input Jupiter is instructed to spend
+        let round_trip = ctx.accounts.source_token.key() == ctx.accounts.destination_token.key();
+        let round_trip_volume = if round_trip {
+            let route = route
+                .filter(|route| route.kind != JupiterRouteKind::ExactOutRoute)
+                .ok_or(ErrorCode::RoundTripRequiresRoute)?;
+            require!(route.in_amount == amount_in, ErrorCode::JupiterAmountMismatch);
+            Some(route.in_amount)
+        } else {
+            None
+        };
+        
+        let source_before = ctx.accounts.source_token.amount;
+        let destination_before = ctx.accounts.destination_token.amount;
         
         // Build Jupiter instruction
         let jupiter_ix = Instruction {
-            program_id: JUPITER_V6,
-            accounts: ctx.remaining_accounts
-                .iter()
-                .map(|acc| AccountMeta {
-                    pubkey: acc.key(),
-                    is_signer: acc.is_signer,
-                    is_writable: acc.is_writable,
-                })
-                .collect(),
+            program_id: ctx.accounts.jupiter_program.key(),
+            accounts: sanitize_remaining_accounts(
+                ctx.remaining_accounts,
+                &ctx.accounts.vault_authority.key(),
+                &ctx.accounts.router_state.key(),
+            )?,
             data: jupiter_instruction_data,
         };
         
-        // Convert vault_seeds to proper format
-        let seed_slices: Vec<&[u8]> = vault_seeds.iter().map(|s| s.as_slice()).collect();
-        let signer_seeds = &[seed_slices.as_slice()];
+        // Vault PDA signature is inherited from the vault program's CPI
+        invoke(&jupiter_ix, ctx.remaining_accounts)?;
         
-        // Execute with vault PDA signing
-        invoke_signed(&jupiter_ix, ctx.remaining_accounts, signer_seeds)?;
+        ctx.accounts.source_token.reload()?;
+        ctx.accounts.destination_token.reload()?;
+        
+        let amount_spent = source_before.saturating_sub(ctx.accounts.source_token.amount);
+        require!(amount_spent <= amount_in, ErrorCode::SwapInputExceeded);
+        
+        let volume = round_trip_volume.unwrap_or(amount_spent);
+        let amount_out = ctx.accounts.destination_token.amount.saturating_sub(destination_before);
+        
+        let clock = Clock::get()?;
+        let router = &mut ctx.accounts.router_state;
+        router.record_swap(&mut ctx.accounts.router_metrics, &clock, volume, 0)?;
+        
+        let mint_stats = &mut ctx.accounts.mint_stats;
+        mint_stats.mint = ctx.accounts.source_token.mint;
+        mint_stats.total_volume = mint_stats.total_volume.checked_add(volume)
+            .ok_or(ErrorCode::MathOverflow)?;
+        mint_stats.bump = ctx.bumps.mint_stats;
+        
+        msg!(" Vault Jupiter swap completed. Volume: {}", volume);
+        
+        emit!(VaultSwapExecuted {
+            vault: ctx.accounts.vault_authority.key(),
+            input_mint: ctx.accounts.source_token.mint,
+            output_mint: ctx.accounts.destination_token.mint,
+            amount_in: volume,
+            amount_out,
+            route_program: ctx.accounts.jupiter_program.key(),
+        });
         
-        msg!(" Vault Jupiter swap completed");
         Ok(())
     }
 
-    /// Get router statistics
-    pub fn get_stats(ctx: Context<GetStats>) -> Result<RouterStats> {
-        let router = &ctx.accounts.router_state;
+    /// Swap directly against an SPL token-swap constant-product pool
+    ///
+    /// The router fee is charged on the input side at the same resolved rate and
+    /// referral split as `execute_jupiter_swap`, the expected output is computed
+    /// from the pool reserves, and both the quote and the measured output must
+    /// reach `min_amount_out`.
+    pub fn swap_constant_product(
+        ctx: Context<SwapConstantProduct>,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        msg!(" Executing constant-product swap");
         
-        Ok(RouterStats {
-            authority: router.authority,
-            fee_rate_bps: router.fee_rate_bps,
-            total_swaps: router.total_swaps,
-            total_volume: router.total_volume,
-        })
+        require!(amount_in > 0, ErrorCode::InvalidSwapAmount);
+        
+        let swap_program = ctx.accounts.swap_program.key();
+        validate_dex_program(&ctx.accounts.dex_registry, &swap_program)?;
+        validate_mint_extensions(&ctx.accounts.input_mint.to_account_info())?;
+        validate_mint_extensions(&ctx.accounts.output_mint.to_account_info())?;
+        
+        // Validate pool state against the typed reserve accounts
+        require!(ctx.accounts.pool.owner == &swap_program, ErrorCode::PoolNotFound);
+        let pool = TokenSwapPool::unpack(&ctx.accounts.pool.try_borrow_data()?)?;
+        require!(pool.curve_type == CURVE_TYPE_CONSTANT_PRODUCT, ErrorCode::UnsupportedPoolCurve);
+        
+        let source_mint = ctx.accounts.user_source_token.mint;
+        let (input_mint, output_mint) = pool.direction(
+            &ctx.accounts.reserve_in.key(),
+            &ctx.accounts.reserve_out.key(),
+        )?;
+        require_keys_eq!(source_mint, input_mint, ErrorCode::InvalidTokenPair);
+        
+        require_keys_eq!(ctx.accounts.pool_mint.key(), pool.pool_mint, ErrorCode::PoolMismatch);
+        require_keys_eq!(ctx.accounts.pool_fee_account.key(), pool.pool_fee_account, ErrorCode::PoolMismatch);
+        require_keys_eq!(ctx.accounts.token_program.key(), pool.token_program_id, ErrorCode::PoolMismatch);
+        require_keys_eq!(
+            ctx.accounts.user_destination_token.mint,
+            output_mint,
+            ErrorCode::TokenMintMismatch
+        );
+        
+        let pool_authority = Pubkey::create_program_address(
+            &[ctx.accounts.pool.key().as_ref(), &[pool.bump_seed]],
+            &swap_program,
+        )
+        .map_err(|_| ErrorCode::PoolMismatch)?;
+        require_keys_eq!(ctx.accounts.pool_authority.key(), pool_authority, ErrorCode::PoolMismatch);
+        
+        // Collect the router fee from the input amount
+        let clock = Clock::get()?;
+        let fee_rate_bps = ctx.accounts
+            .fee_accounts()
+            .fee_rate(ctx.accounts.router_state.fee_rate_bps, clock.unix_timestamp);
+        let fee = calculate_fee(amount_in, fee_rate_bps)?;
+        let swap_amount = calculate_net_amount(amount_in, fee_rate_bps)?;
+        
+        let fee_received = ctx.accounts.fee_accounts().collect(fee)?;
+        
+        // Quote from reserves before touching the pool
+        let expected_out = pool.swap_output(
+            swap_amount,
+            ctx.accounts.reserve_in.amount,
+            ctx.accounts.reserve_out.amount,
+        )?;
+        require!(expected_out > 0, ErrorCode::InsufficientOutputAmount);
+        require!(expected_out >= min_amount_out, ErrorCode::SlippageExceeded);
+        
+        let destination_before = ctx.accounts.user_destination_token.amount;
+        
+        let swap_ix = Instruction {
+            program_id: swap_program,
+            accounts: vec![
+                AccountMeta::new_readonly(ctx.accounts.pool.key(), false),
+                AccountMeta::new_readonly(pool_authority, false),
+                AccountMeta::new_readonly(ctx.accounts.user.key(), true),
+                AccountMeta::new(ctx.accounts.user_source_token.key(), false),
+                AccountMeta::new(ctx.accounts.reserve_in.key(), false),
+                AccountMeta::new(ctx.accounts.reserve_out.key(), false),
+                AccountMeta::new(ctx.accounts.user_destination_token.key(), false),
+                AccountMeta::new(ctx.accounts.pool_mint.key(), false),
+                AccountMeta::new(ctx.accounts.pool_fee_account.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
+            ],
+            data: swap_instruction_data(swap_amount, min_amount_out),
+        };
+        
+        invoke(
+            &swap_ix,
+            &[
+                ctx.accounts.pool.to_account_info(),
+                ctx.accounts.pool_authority.to_account_info(),
+                ctx.accounts.user.to_account_info(),
+                ctx.accounts.user_source_token.to_account_info(),
+                ctx.accounts.reserve_in.to_account_info(),
+                ctx.accounts.reserve_out.to_account_info(),
+                ctx.accounts.user_destination_token.to_account_info(),
+                ctx.accounts.pool_mint.to_account_info(),
+                ctx.accounts.pool_fee_account.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.swap_program.to_account_info(),
+            ],
+        )?;
+        
+        ctx.accounts.user_destination_token.reload()?;
+        let amount_out = ctx.accounts.user_destination_token.amount
+            .checked_sub(destination_before)
+            .ok_or(ErrorCode::SwapFailed)?;
+        require!(amount_out >= min_amount_out, ErrorCode::SlippageExceeded);
+        
+        let router = &mut ctx.accounts.router_state;
+        router.record_swap(&mut ctx.accounts.router_metrics, &clock, swap_amount, fee_received)?;
+        
+        let mint_stats = &mut ctx.accounts.mint_stats;
+        mint_stats.mint = source_mint;
+        mint_stats.total_volume = mint_stats.total_volume.checked_add(swap_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        mint_stats.bump = ctx.bumps.mint_stats;
+        
+        if let Some(user_stats) = ctx.accounts.user_stats.as_mut() {
+            user_stats.record_swap(source_mint, swap_amount, fee_received, clock.slot, clock.unix_timestamp)?;
+        }
+        
+        msg!(
+            " Constant-product swap completed. In: {}, Out: {}, Expected: {}, Fee: {}",
+            swap_amount,
+            amount_out,
+            expected_out,
+            fee_received
+        );
+        
+        emit!(SwapExecuted {
+            user: ctx.accounts.user.key(),
+            input_mint: source_mint,
+            output_mint,
+            amount_in: swap_amount,
+            amount_out,
+            fee: fee_received,
+            route_program: swap_program,
+        });
+        
+        Ok(())
     }
-}
 
-// ========== ACCOUNT STRUCTURES ==========
+    /// Execute a multi-hop route composed of sequential CPIs
+    ///
+    /// Each leg CPIs into a registered program using a slice of `remaining_accounts`.
+    /// The leg's output token account must hold the expected intermediate mint, be
+    /// owned by the user, and increase in balance before the next leg runs. The last
+    /// leg must pay into `user_destination_token`. The router fee is charged on the
+    /// input side as in `execute_jupiter_swap`, and Jupiter V6 legs must decode to a
+    /// route between the leg's input and output accounts that spends at most the
+    /// previous leg's output.
+    pub fn execute_route<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteRoute<'info>>,
+        legs: Vec<RouteLeg>,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        msg!(" Executing route with {} legs", legs.len());
+        
+        require!(amount_in > 0, ErrorCode::InvalidSwapAmount);
+        require!(
+            !legs.is_empty() && legs.len() <= RouteLeg::MAX_LEGS,
+            ErrorCode::InvalidRoute
+        );
+        
+        validate_mint_extensions(&ctx.accounts.input_mint.to_account_info())?;
+        validate_mint_extensions(&ctx.accounts.output_mint.to_account_info())?;
+        
+        let user = ctx.accounts.user.key();
+        let destination = &ctx.accounts.user_destination_token;
+        let last_leg = &legs[legs.len() - 1];
+        require!(
+            last_leg.output_mint == destination.mint
+                && ctx.remaining_accounts
+                    .get(last_leg.output_token_index as usize)
+                    .map(|acc| acc.key())
+                    == Some(destination.key()),
+            ErrorCode::InvalidRoute
+        );
+        
+        // Collect the router fee from the input amount
+        let clock = Clock::get()?;
+        let fee_rate_bps = ctx.accounts
+            .fee_accounts()
+            .fee_rate(ctx.accounts.router_state.fee_rate_bps, clock.unix_timestamp);
+        let fee = calculate_fee(amount_in, fee_rate_bps)?;
+        let swap_amount = calculate_net_amount(amount_in, fee_rate_bps)?;
+        
+        let fee_received = ctx.accounts.fee_accounts().collect(fee)?;
+        if fee > 0 {
+            ctx.accounts.user_source_token.reload()?;
+        }
+        
+        let account_metas = sanitize_remaining_accounts(
+            ctx.remaining_accounts,
+            &user,
+            &ctx.accounts.router_state.key(),
+        )?;
+        
+        let source_before = ctx.accounts.user_source_token.amount;
+        let destination_before = ctx.accounts.user_destination_token.amount;
+        
+        // Token account and amount each leg spends from: the user's source for the
+        // first leg, then the previous leg's output
+        let mut leg_source = ctx.accounts.user_source_token.key();
+        let mut leg_max_in = swap_amount;
+        
+        for (index, leg) in legs.iter().enumerate() {
+            validate_dex_program(&ctx.accounts.dex_registry, &leg.program_id)?;
+            
+            let start = leg.accounts_start as usize;
+            let end = start
+                .checked_add(leg.accounts_len as usize)
+                .ok_or(ErrorCode::InvalidRoute)?;
+            let leg_accounts = ctx.remaining_accounts
+                .get(start..end)
+                .ok_or(ErrorCode::InvalidRoute)?;
+            let program = ctx.remaining_accounts
+                .iter()
+                .find(|acc| acc.key() == leg.program_id)
+                .ok_or(ErrorCode::InvalidRoute)?;
+            let output = ctx.remaining_accounts
+                .get(leg.output_token_index as usize)
+                .ok_or(ErrorCode::InvalidRoute)?;
+            
+            let output_before = read_token_account(output, &leg.output_mint, &user)?.amount;
+            
+            // Jupiter legs are held to the same payload checks as execute_jupiter_swap
+            if leg.program_id == JUPITER_V6 {
+                let route = JupiterRoute::parse(&leg.data)?;
+                route.validate_accounts(leg_accounts, &leg_source, &output.key())?;
+                let leg_min_out = if index == legs.len() - 1 { min_amount_out } else { 0 };
+                route.validate_amounts(leg_max_in, leg_min_out)?;
+            }
+            
+            let leg_ix = Instruction {
+                program_id: leg.program_id,
+                accounts: account_metas[start..end].to_vec(),
+                data: leg.data.clone(),
+            };
+            
+            let mut leg_infos = leg_accounts.to_vec();
+            leg_infos.push(program.clone());
+            invoke(&leg_ix, &leg_infos)?;
+            
+            let output_after = read_token_account(output, &leg.output_mint, &user)?.amount;
+            require!(output_after > output_before, ErrorCode::InvalidRoute);
+            
+            leg_source = output.key();
+            leg_max_in = output_after - output_before;
+        }
+        
+        ctx.accounts.user_source_token.reload()?;
+        ctx.accounts.user_destination_token.reload()?;
+        
+        let amount_swapped = source_before
+            .checked_sub(ctx.accounts.user_source_token.amount)
+            .ok_or(ErrorCode::SwapFailed)?;
+        let amount_out = ctx.accounts.user_destination_token.amount
+            .checked_sub(destination_before)
+            .ok_or(ErrorCode::SwapFailed)?;
+        
+        require!(amount_swapped <= swap_amount, ErrorCode::SwapInputExceeded);
+        require!(amount_out >= min_amount_out, ErrorCode::SlippageExceeded);
+        
+        let router = &mut ctx.accounts.router_state;
+        router.record_swap(&mut ctx.accounts.router_metrics, &clock, amount_swapped, fee_received)?;
+        
+        let mint_stats = &mut ctx.accounts.mint_stats;
+        mint_stats.mint = ctx.accounts.input_mint.key();
+        mint_stats.total_volume = mint_stats.total_volume.checked_add(amount_swapped)
+            .ok_or(ErrorCode::MathOverflow)?;
+        mint_stats.bump = ctx.bumps.mint_stats;
+        
+        if let Some(user_stats) = ctx.accounts.user_stats.as_mut() {
+            user_stats.record_swap(
+                ctx.accounts.input_mint.key(),
+                amount_swapped,
+                fee_received,
+                clock.slot,
+                clock.unix_timestamp,
+            )?;
+        }
+        
+        msg!(
+            " Route completed. In: {}, Out: {}, Fee: {}, Legs: {}",
+            amount_swapped,
+            amount_out,
+            fee_received,
+            legs.len()
+        );
+        
+        emit!(RouteExecuted {
+            user,
+            input_mint: ctx.accounts.input_mint.key(),
+            output_mint: ctx.accounts.user_destination_token.mint,
+            amount_in: amount_swapped,
+            amount_out,
+            fee: fee_received,
+            route_programs: legs.iter().map(|leg| leg.program_id).collect(),
+        });
+        
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct InitializeRouter<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + RouterState::INIT_SPACE,
-        seeds = [b"router_state"],
-        bump
-    )]
-    pub router_state: Account<'info, RouterState>,
-    
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    pub system_program: Program<'info, System>,
-}
+    /// Update the router fee rate (authority only)
+    pub fn set_fee_rate(ctx: Context<UpdateRouter>, fee_rate_bps: u16) -> Result<()> {
+        require!(fee_rate_bps <= 1000, ErrorCode::InvalidFeeRate);
+        
+        let router = &mut ctx.accounts.router_state;
+        let old_fee_rate_bps = router.fee_rate_bps;
+        router.fee_rate_bps = fee_rate_bps;
+        
+        emit!(router.config_changed());
+        
+        msg!(" Router fee updated: {} -> {} bps", old_fee_rate_bps, fee_rate_bps);
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct ExecuteJupiterSwap<'info> {
-    #[account(
-        mut,
-        seeds = [b"router_state"],
-        bump = router_state.bump
-    )]
-    pub router_state: Account<'info, RouterState>,
-    
-    /// User executing the swap
-    #[account(mut)]
-    pub user: Signer<'info>,
-    
-    /// CHECK: Jupiter V6 Program - validated in instruction
-    #[account(constraint = jupiter_program.key() == JUPITER_V6)]
-    pub jupiter_program: AccountInfo<'info>,
-    
-    // All other accounts (token accounts, mints, programs, etc.)
-    // are passed via remaining_accounts
-    // Jupiter API tells  you which accounts to include
-}
+    /// Propose a new router authority (step 1 of 2, authority only)
+    pub fn propose_authority(ctx: Context<UpdateRouter>, new_authority: Pubkey) -> Result<()> {
+        let router = &mut ctx.accounts.router_state;
+        router.pending_authority = new_authority;
+        
+        emit!(router.config_changed());
+        
+        msg!(" Router authority proposed: {}", new_authority);
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct ExecuteVaultJupiterSwap<'info> {
-    #[account(
-        mut,
-        seeds = [b"router_state"],
-        bump = router_state.bump
-    )]
-    pub router_state: Account<'info, RouterState>,
-    
-    /// Vault authority (PDA)
-    /// CHECK: Validated by vault program
-    pub vault_authority: AccountInfo<'info>,
-    
-    /// CHECK: Jupiter V6 Program
-    #[account(constraint = jupiter_program.key() == JUPITER_V6)]
-    pub jupiter_program: AccountInfo<'info>,
-}
+    /// Accept a pending authority transfer (step 2 of 2, pending authority only)
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let router = &mut ctx.accounts.router_state;
+        router.authority = ctx.accounts.pending_authority.key();
+        router.pending_authority = Pubkey::default();
+        
+        emit!(router.config_changed());
+        
+        msg!(" Router authority accepted: {}", router.authority);
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct GetStats<'info> {
-    #[account(seeds = [b"router_state"], bump = router_state.bump)]
-    pub router_state: Account<'info, RouterState>,
-}
+    /// Pause or resume swap execution (authority only)
+    pub fn set_paused(ctx: Context<UpdateRouter>, paused: bool) -> Result<()> {
+        let router = &mut ctx.accounts.router_state;
+        router.paused = paused;
+        
+        emit!(router.config_changed());
+        
+        msg!(" Router paused: {}", paused);
+        Ok(())
+    }
 
-// ========== STATE ==========
+    /// Configure the swap volume caps that trip the circuit breaker
+    /// (authority only, 0 disables a cap)
+    pub fn set_circuit_breaker(
+        ctx: Context<UpdateRouter>,
+        max_slot_volume: u64,
+        max_hourly_volume: u64,
+    ) -> Result<()> {
+        let router = &mut ctx.accounts.router_state;
+        router.max_slot_volume = max_slot_volume;
+        router.max_hourly_volume = max_hourly_volume;
+        
+        emit!(router.config_changed());
+        
+        msg!(
+            " Circuit breaker caps set: {} per slot, {} per hour",
+            max_slot_volume,
+            max_hourly_volume
+        );
+        Ok(())
+    }
 
-#[account]
-pub struct RouterState {
-    pub authority: Pubkey,      // 32
-    pub fee_rate_bps: u16,      // 2  (basis points, 100 = 1%)
-    pub total_swaps: u64,       // 8
-    pub total_volume: u64,      // 8
-    pub bump: u8,               // 1
-}
+    /// Restrict protocol fee withdrawals to token accounts owned by `treasury`
+    /// (authority only, `Pubkey::default()` clears the restriction)
+    pub fn set_treasury(ctx: Context<UpdateRouter>, treasury: Pubkey) -> Result<()> {
+        let router = &mut ctx.accounts.router_state;
+        router.treasury = treasury;
+        
+        emit!(router.config_changed());
+        
+        msg!(" Router treasury set: {}", treasury);
+        Ok(())
+    }
 
-impl RouterState {
-    pub const INIT_SPACE: usize = 32 + 2 + 8 + 8 + 1;
-}
+    /// Withdraw accrued protocol fees for one mint (authority only)
+    pub fn withdraw_protocol_fees(ctx: Context<WithdrawProtocolFees>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidSwapAmount);
+        require!(
+            ctx.accounts.fee_vault.amount >= amount,
+            ErrorCode::InsufficientFeeBalance
+        );
+        
+        let router_bump = ctx.accounts.router_state.bump;
+        let seeds = &[b"router_state".as_ref(), &[router_bump]];
+        let signer_seeds = &[&seeds[..]];
+        
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.fee_vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.router_state.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+        
+        emit!(FeesWithdrawn {
+            authority: ctx.accounts.authority.key(),
+            mint: ctx.accounts.mint.key(),
+            destination: ctx.accounts.destination.key(),
+            amount,
+        });
+        
+        msg!(" Protocol fees withdrawn: {}", amount);
+        Ok(())
+    }
 
-// ========== RETURN TYPES ==========
+    /// Create the DEX registry, seeded with Jupiter V6 (authority only)
+    pub fn initialize_dex_registry(ctx: Context<InitializeDexRegistry>) -> Result<()> {
+        let registry = &mut ctx.accounts.dex_registry;
+        registry.bump = ctx.bumps.dex_registry;
+        registry.programs = vec![DexProgram {
+            program_id: JUPITER_V6,
+            enabled: true,
+        }];
+        
+        emit!(DexRegistryChanged {
+            program_id: JUPITER_V6,
+            registered: true,
+            enabled: true,
+        });
+        
+        msg!(" DEX registry initialized with Jupiter V6");
+        Ok(())
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct RouterStats {
-    pub authority: Pubkey,
-    pub fee_rate_bps: u16,
-    pub total_swaps: u64,
-    pub total_volume: u64,
-}
+    /// Create the hourly metrics ring buffer (authority only)
+    pub fn initialize_router_metrics(ctx: Context<InitializeRouterMetrics>) -> Result<()> {
+        let metrics = &mut ctx.accounts.router_metrics;
+        metrics.bump = ctx.bumps.router_metrics;
+        metrics.buckets = vec![HourlyBucket::default(); RouterMetrics::BUCKETS];
+        
+        msg!(" Router metrics initialized with {} hourly buckets", RouterMetrics::BUCKETS);
+        Ok(())
+    }
 
-// ========== ERRORS ==========
+    /// Register a new DEX/aggregator program (authority only)
+    pub fn add_dex_program(ctx: Context<UpdateDexRegistry>, program_id: Pubkey) -> Result<()> {
+        let registry = &mut ctx.accounts.dex_registry;
+        
+        require!(
+            registry.find(&program_id).is_none(),
+            ErrorCode::DexProgramAlreadyRegistered
+        );
+        require!(
+            registry.programs.len() < DexRegistry::MAX_PROGRAMS,
+            ErrorCode::DexRegistryFull
+        );
+        
+        registry.programs.push(DexProgram {
+            program_id,
+            enabled: true,
+        });
+        
+        emit!(DexRegistryChanged {
+            program_id,
+            registered: true,
+            enabled: true,
+        });
+        
+        msg!(" DEX program registered: {}", program_id);
+        Ok(())
+    }
 
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Invalid Jupiter program provided")]
-    InvalidJupiterProgram,
-    
-    #[msg("Invalid fee rate - must be <= 1000 bps (10%)")]
-    InvalidFeeRate,
+    /// Enable or disable a registered DEX/aggregator program (authority only)
+    pub fn set_dex_program_enabled(
+        ctx: Context<UpdateDexRegistry>,
+        program_id: Pubkey,
+        enabled: bool,
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.dex_registry;
+        let index = registry.find(&program_id).ok_or(ErrorCode::DexProgramNotRegistered)?;
+        registry.programs[index].enabled = enabled;
+        
+        emit!(DexRegistryChanged {
+            program_id,
+            registered: true,
+            enabled,
+        });
+        
+        msg!(" DEX program {} enabled: {}", program_id, enabled);
+        Ok(())
+    }
+
+    /// Remove a DEX/aggregator program from the registry (authority only)
+    pub fn remove_dex_program(ctx: Context<UpdateDexRegistry>, program_id: Pubkey) -> Result<()> {
+        let registry = &mut ctx.accounts.dex_registry;
+        let index = registry.find(&program_id).ok_or(ErrorCode::DexProgramNotRegistered)?;
+        registry.programs.remove(index);
+        
+        emit!(DexRegistryChanged {
+            program_id,
+            registered: false,
+            enabled: false,
+        });
+        
+        msg!(" DEX program removed: {}", program_id);
+        Ok(())
+    }
+
+    /// Set the volume-tiered fee schedule for an input mint (authority only)
+    ///
+    /// Tier thresholds are in raw units of the mint and are matched against the
+    /// user's 30-day volume in that mint.
+    pub fn set_fee_schedule(ctx: Context<SetFeeSchedule>, tiers: Vec<FeeTier>) -> Result<()> {
+        require!(tiers.len() <= FeeSchedule::MAX_TIERS, ErrorCode::InvalidFeeSchedule);
+        require!(
+            tiers.windows(2).all(|pair| pair[0].min_volume < pair[1].min_volume),
+            ErrorCode::InvalidFeeSchedule
+        );
+        // Swaps may omit the schedule, so tiers can only discount the base rate
+        require!(
+            tiers.iter().all(|tier| tier.fee_rate_bps <= ctx.accounts.router_state.fee_rate_bps),
+            ErrorCode::InvalidFeeRate
+        );
+        
+        let fee_schedule = &mut ctx.accounts.fee_schedule;
+        fee_schedule.mint = ctx.accounts.mint.key();
+        fee_schedule.bump = ctx.bumps.fee_schedule;
+        fee_schedule.tiers = tiers;
+        
+        emit!(FeeScheduleChanged {
+            mint: fee_schedule.mint,
+            tiers: fee_schedule.tiers.clone(),
+        });
+        
+        msg!(" Fee schedule set for {}: {} tiers", fee_schedule.mint, fee_schedule.tiers.len());
+        Ok(())
+    }
+
+    /// Grant a wallet a fixed fee rate (authority only)
+    pub fn set_fee_override(
+        ctx: Context<SetFeeOverride>,
+        wallet: Pubkey,
+        fee_rate_bps: u16,
+    ) -> Result<()> {
+        // Swaps may omit the override, so it can only discount the base rate
+        require!(
+            fee_rate_bps <= ctx.accounts.router_state.fee_rate_bps,
+            ErrorCode::InvalidFeeRate
+        );
+        
+        let fee_override = &mut ctx.accounts.fee_override;
+        fee_override.wallet = wallet;
+        fee_override.fee_rate_bps = fee_rate_bps;
+        fee_override.bump = ctx.bumps.fee_override;
+        
+        emit!(FeeOverrideChanged {
+            wallet,
+            fee_rate_bps,
+            active: true,
+        });
+        
+        msg!(" Fee override for {}: {} bps", wallet, fee_rate_bps);
+        Ok(())
+    }
+
+    /// Revoke a wallet fee override (authority only)
+    pub fn remove_fee_override(ctx: Context<RemoveFeeOverride>) -> Result<()> {
+        let fee_override = &ctx.accounts.fee_override;
+        
+        emit!(FeeOverrideChanged {
+            wallet: fee_override.wallet,
+            fee_rate_bps: fee_override.fee_rate_bps,
+            active: false,
+        });
+        
+        msg!(" Fee override removed for {}", fee_override.wallet);
+        Ok(())
+    }
+
+    /// Register or update a referrer and its share of the router fee (authority only)
+    pub fn register_referrer(
+        ctx: Context<RegisterReferrer>,
+        wallet: Pubkey,
+        share_bps: u16,
+    ) -> Result<()> {
+        require!(share_bps <= 10000, ErrorCode::InvalidReferralShare);
+        
+        let referrer = &mut ctx.accounts.referrer;
+        referrer.wallet = wallet;
+        referrer.share_bps = share_bps;
+        referrer.bump = ctx.bumps.referrer;
+        
+        emit!(ReferrerRegistered {
+            referrer: wallet,
+            share_bps,
+        });
+        
+        msg!(" Referrer {} registered with {} bps share", wallet, share_bps);
+        Ok(())
+    }
+
+    /// Create the router-owned referral fee account for a referrer and mint
+    pub fn initialize_referral_fee_vault(_ctx: Context<InitializeReferralFeeVault>) -> Result<()> {
+        msg!(" Referral fee vault initialized");
+        Ok(())
+    }
+
+    /// Withdraw all accrued referral fees for one mint (referrer only)
+    pub fn claim_referral_fees(ctx: Context<ClaimReferralFees>) -> Result<()> {
+        let amount = ctx.accounts.referral_fee_vault.amount;
+        require!(amount > 0, ErrorCode::InvalidSwapAmount);
+        
+        let router_bump = ctx.accounts.router_state.bump;
+        let seeds = &[b"router_state".as_ref(), &[router_bump]];
+        let signer_seeds = &[&seeds[..]];
+        
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.referral_fee_vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.router_state.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+        
+        emit!(ReferralFeesClaimed {
+            referrer: ctx.accounts.referrer.wallet,
+            mint: ctx.accounts.mint.key(),
+            amount,
+        });
+        
+        msg!(" Referral fees claimed: {}", amount);
+        Ok(())
+    }
+
+    /// Opt in to per-user swap statistics
+    pub fn initialize_user_stats(ctx: Context<InitializeUserStats>) -> Result<()> {
+        let user_stats = &mut ctx.accounts.user_stats;
+        user_stats.user = ctx.accounts.user.key();
+        user_stats.swap_count = 0;
+        user_stats.last_swap_slot = 0;
+        user_stats.bump = ctx.bumps.user_stats;
+        user_stats.mints = Vec::new();
+        
+        msg!(" User stats initialized for {}", user_stats.user);
+        Ok(())
+    }
+
+    /// Quote a constant-product swap as `swap_constant_product` would execute it at
+    /// the base fee rate (wallet overrides and volume tiers apply at execution)
+    pub fn quote_constant_product(
+        ctx: Context<QuoteConstantProduct>,
+        amount_in: u64,
+    ) -> Result<ConstantProductQuote> {
+        require!(amount_in > 0, ErrorCode::InvalidSwapAmount);
+        
+        require!(
+            ctx.accounts.pool.owner == &ctx.accounts.swap_program.key(),
+            ErrorCode::PoolNotFound
+        );
+        let pool = TokenSwapPool::unpack(&ctx.accounts.pool.try_borrow_data()?)?;
+        require!(pool.curve_type == CURVE_TYPE_CONSTANT_PRODUCT, ErrorCode::UnsupportedPoolCurve);
+        pool.direction(&ctx.accounts.reserve_in.key(), &ctx.accounts.reserve_out.key())?;
+        
+        let reserve_in = ctx.accounts.reserve_in.amount;
+        let reserve_out = ctx.accounts.reserve_out.amount;
+        
+        let fee_rate_bps = ctx.accounts.router_state.fee_rate_bps;
+        let router_fee = calculate_fee(amount_in, fee_rate_bps)?;
+        let swap_amount = calculate_net_amount(amount_in, fee_rate_bps)?;
+        
+        let pool_fee = pool.trading_fees(swap_amount)?;
+        let amount_out = pool.swap_output(swap_amount, reserve_in, reserve_out)?;
+        let price_impact_bps = calculate_price_impact(
+            swap_amount.saturating_sub(pool_fee),
+            amount_out,
+            reserve_in,
+            reserve_out,
+        )?;
+        
+        Ok(ConstantProductQuote {
+            amount_out,
+            router_fee,
+            pool_fee,
+            price_impact_bps,
+        })
+    }
+
+    /// Get router statistics
+    pub fn get_stats(ctx: Context<GetStats>) -> Result<RouterStats> {
+        let router = &ctx.accounts.router_state;
+        
+        Ok(RouterStats {
+            authority: router.authority,
+            fee_rate_bps: router.fee_rate_bps,
+            total_swaps: router.total_swaps,
+            total_volume: router.total_volume,
+            paused: router.paused,
+            version: router.version,
+        })
+    }
+
+    /// Get swap count, volume and fees over the last `hours` hours (1 to 168),
+    /// including the current partial hour
+    pub fn get_stats_window(ctx: Context<GetStatsWindow>, hours: u16) -> Result<StatsWindow> {
+        require!(
+            hours > 0 && hours as usize <= RouterMetrics::BUCKETS,
+            ErrorCode::InvalidStatsWindow
+        );
+        
+        ctx.accounts.router_metrics.window(Clock::get()?.unix_timestamp, hours)
+    }
+
+    /// Get per-user swap statistics
+    pub fn get_user_stats(ctx: Context<GetUserStats>) -> Result<UserStats> {
+        let user_stats = &ctx.accounts.user_stats;
+        
+        Ok(UserStats {
+            user: user_stats.user,
+            swap_count: user_stats.swap_count,
+            last_swap_slot: user_stats.last_swap_slot,
+            mints: user_stats.mints.clone(),
+        })
+    }
+}
+
+// ========== ACCOUNT STRUCTURES ==========
+
+#[derive(Accounts)]
+pub struct InitializeRouter<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RouterState::INIT_SPACE,
+        seeds = [b"router_state"],
+        bump
+    )]
+    pub router_state: Account<'info, RouterState>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateRouterState<'info> {
+    /// CHECK: Legacy-layout router state, decoded and rewritten in the instruction
+    #[account(
+        mut,
+        seeds = [b"router_state"],
+        bump,
+        owner = crate::ID
+    )]
+    pub router_state: UncheckedAccount<'info>,
+    
+    /// Funds the rent for the larger account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteJupiterSwap<'info> {
+    #[account(
+        mut,
+        seeds = [b"router_state"],
+        bump = router_state.bump,
+        constraint = !router_state.paused @ ErrorCode::RouterPaused
+    )]
+    pub router_state: Account<'info, RouterState>,
+    
+    /// Hourly swap metrics
+    #[account(
+        mut,
+        seeds = [b"router_metrics"],
+        bump = router_metrics.bump
+    )]
+    pub router_metrics: Account<'info, RouterMetrics>,
+    
+    /// User executing the swap
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    /// User's token account for the input mint (router fee is taken from here)
+    #[account(
+        mut,
+        token::mint = input_mint,
+        token::authority = user
+    )]
+    pub user_source_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub input_mint: InterfaceAccount<'info, Mint>,
+    
+    /// Router-owned fee account for the input mint
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"fee_vault", input_mint.key().as_ref()],
+        bump,
+        token::mint = input_mint,
+        token::authority = router_state,
+        token::token_program = token_program
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// User's token account receiving the swap output
+    #[account(
+        mut,
+        token::mint = output_mint,
+        token::authority = user
+    )]
+    pub user_destination_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub output_mint: InterfaceAccount<'info, Mint>,
+    
+    /// Per-mint volume stats for the input mint
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + MintStats::INIT_SPACE,
+        seeds = [b"mint_stats", input_mint.key().as_ref()],
+        bump
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    /// Optional per-user stats, updated when the user has opted in
+    #[account(
+        mut,
+        seeds = [b"user_stats", user.key().as_ref()],
+        bump = user_stats.bump
+    )]
+    pub user_stats: Option<Account<'info, UserSwapStats>>,
+    
+    /// Optional volume-tiered fee schedule for the input mint
+    #[account(
+        seeds = [b"fee_schedule", input_mint.key().as_ref()],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Option<Account<'info, FeeSchedule>>,
+    
+    /// Optional authority-granted fee rate for this user
+    #[account(
+        seeds = [b"fee_override", user.key().as_ref()],
+        bump = fee_override.bump
+    )]
+    pub fee_override: Option<Account<'info, FeeOverride>>,
+    
+    /// Optional registered referrer sharing the router fee
+    #[account(
+        seeds = [b"referrer", referrer.wallet.as_ref()],
+        bump = referrer.bump
+    )]
+    pub referrer: Option<Account<'info, Referrer>>,
+    
+    /// Router-owned referral fee account for the referrer and input mint
+    #[account(
+        mut,
+        seeds = [
+            b"referral_fees",
+            referrer.as_ref().ok_or(ErrorCode::InvalidReferrer)?.key().as_ref(),
+            input_mint.key().as_ref()
+        ],
+        bump,
+        token::mint = input_mint,
+        token::authority = router_state
+    )]
+    pub referral_fee_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(seeds = [b"dex_registry"], bump = dex_registry.bump)]
+    pub dex_registry: Account<'info, DexRegistry>,
+    
+    /// CHECK: Target DEX/aggregator program - validated against dex_registry in instruction
+    #[account(executable)]
+    pub jupiter_program: AccountInfo<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    
+    // All other accounts (token accounts, mints, programs, etc.)
+    // are passed via remaining_accounts
+    // Jupiter API tells  you which accounts to include
+}
+
+impl<'info> ExecuteJupiterSwap<'info> {
+    pub fn fee_accounts(&self) -> RouterFeeAccounts<'_, 'info> {
+        RouterFeeAccounts {
+            user: &self.user,
+            user_source_token: &self.user_source_token,
+            input_mint: &self.input_mint,
+            fee_vault: &self.fee_vault,
+            fee_override: self.fee_override.as_ref(),
+            fee_schedule: self.fee_schedule.as_ref(),
+            user_stats: self.user_stats.as_ref(),
+            referrer: self.referrer.as_ref(),
+            referral_fee_vault: self.referral_fee_vault.as_ref(),
+            token_program: &self.token_program,
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct ExecuteVaultJupiterSwap<'info> {
+    #[account(
+        mut,
+        seeds = [b"router_state"],
+        bump = router_state.bump,
+        constraint = !router_state.paused @ ErrorCode::RouterPaused
+    )]
+    pub router_state: Account<'info, RouterState>,
+    
+    /// Hourly swap metrics
+    #[account(
+        mut,
+        seeds = [b"router_metrics"],
+        bump = router_metrics.bump
+    )]
+    pub router_metrics: Account<'info, RouterMetrics>,
+    
+    /// Vault authority (PDA) - must be signed for by the vault program
+    #[account(owner = VAULT_PROGRAM @ ErrorCode::InvalidVaultAuthority)]
+    pub vault_authority: Signer<'info>,
+    
+    /// Vault token account spent by the swap
+    #[account(
+        mut,
+        token::authority = vault_authority
+    )]
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Vault token account receiving the swap output (may equal source_token)
+    #[account(
+        mut,
+        token::mint = output_mint,
+        token::authority = vault_authority
+    )]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub output_mint: InterfaceAccount<'info, Mint>,
+    
+    /// Per-mint volume stats for the source mint
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + MintStats::INIT_SPACE,
+        seeds = [b"mint_stats", source_token.mint.as_ref()],
+        bump
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    /// Pays for the mint stats account on first use
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"dex_registry"], bump = dex_registry.bump)]
+    pub dex_registry: Account<'info, DexRegistry>,
+    
+    /// CHECK: Target DEX/aggregator program - validated against dex_registry in instruction
+    #[account(executable)]
+    pub jupiter_program: AccountInfo<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SwapConstantProduct<'info> {
+    #[account(
+        mut,
+        seeds = [b"router_state"],
+        bump = router_state.bump,
+        constraint = !router_state.paused @ ErrorCode::RouterPaused
+    )]
+    pub router_state: Account<'info, RouterState>,
+    
+    /// Hourly swap metrics
+    #[account(
+        mut,
+        seeds = [b"router_metrics"],
+        bump = router_metrics.bump
+    )]
+    pub router_metrics: Account<'info, RouterMetrics>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        token::mint = input_mint,
+        token::authority = user
+    )]
+    pub user_source_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = output_mint,
+        token::authority = user
+    )]
+    pub user_destination_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub input_mint: InterfaceAccount<'info, Mint>,
+    
+    pub output_mint: InterfaceAccount<'info, Mint>,
+    
+    /// Router-owned fee account for the input mint
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"fee_vault", input_mint.key().as_ref()],
+        bump,
+        token::mint = input_mint,
+        token::authority = router_state,
+        token::token_program = token_program
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Per-mint volume stats for the input mint
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + MintStats::INIT_SPACE,
+        seeds = [b"mint_stats", input_mint.key().as_ref()],
+        bump
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    /// Optional per-user stats, updated when the user has opted in
+    #[account(
+        mut,
+        seeds = [b"user_stats", user.key().as_ref()],
+        bump = user_stats.bump
+    )]
+    pub user_stats: Option<Account<'info, UserSwapStats>>,
+    
+    /// Optional volume-tiered fee schedule for the input mint
+    #[account(
+        seeds = [b"fee_schedule", input_mint.key().as_ref()],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Option<Account<'info, FeeSchedule>>,
+    
+    /// Optional authority-granted fee rate for this user
+    #[account(
+        seeds = [b"fee_override", user.key().as_ref()],
+        bump = fee_override.bump
+    )]
+    pub fee_override: Option<Account<'info, FeeOverride>>,
+    
+    /// Optional registered referrer sharing the router fee
+    #[account(
+        seeds = [b"referrer", referrer.wallet.as_ref()],
+        bump = referrer.bump
+    )]
+    pub referrer: Option<Account<'info, Referrer>>,
+    
+    /// Router-owned referral fee account for the referrer and input mint
+    #[account(
+        mut,
+        seeds = [
+            b"referral_fees",
+            referrer.as_ref().ok_or(ErrorCode::InvalidReferrer)?.key().as_ref(),
+            input_mint.key().as_ref()
+        ],
+        bump,
+        token::mint = input_mint,
+        token::authority = router_state
+    )]
+    pub referral_fee_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// CHECK: Token-swap pool state - owner and layout validated in instruction
+    pub pool: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool authority PDA - re-derived from the pool in instruction
+    pub pool_authority: UncheckedAccount<'info>,
+    
+    /// Pool reserve for the input mint
+    #[account(mut)]
+    pub reserve_in: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Pool reserve for the output mint
+    #[account(mut)]
+    pub reserve_out: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub pool_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut)]
+    pub pool_fee_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(seeds = [b"dex_registry"], bump = dex_registry.bump)]
+    pub dex_registry: Account<'info, DexRegistry>,
+    
+    /// CHECK: Token-swap program - validated against dex_registry in instruction
+    #[account(executable)]
+    pub swap_program: AccountInfo<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SwapConstantProduct<'info> {
+    pub fn fee_accounts(&self) -> RouterFeeAccounts<'_, 'info> {
+        RouterFeeAccounts {
+            user: &self.user,
+            user_source_token: &self.user_source_token,
+            input_mint: &self.input_mint,
+            fee_vault: &self.fee_vault,
+            fee_override: self.fee_override.as_ref(),
+            fee_schedule: self.fee_schedule.as_ref(),
+            user_stats: self.user_stats.as_ref(),
+            referrer: self.referrer.as_ref(),
+            referral_fee_vault: self.referral_fee_vault.as_ref(),
+            token_program: &self.token_program,
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct ExecuteRoute<'info> {
+    #[account(
+        mut,
+        seeds = [b"router_state"],
+        bump = router_state.bump,
+        constraint = !router_state.paused @ ErrorCode::RouterPaused
+    )]
+    pub router_state: Account<'info, RouterState>,
+    
+    /// Hourly swap metrics
+    #[account(
+        mut,
+        seeds = [b"router_metrics"],
+        bump = router_metrics.bump
+    )]
+    pub router_metrics: Account<'info, RouterMetrics>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        token::mint = input_mint,
+        token::authority = user
+    )]
+    pub user_source_token: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Receives the output of the final leg
+    #[account(
+        mut,
+        token::mint = output_mint,
+        token::authority = user
+    )]
+    pub user_destination_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub input_mint: InterfaceAccount<'info, Mint>,
+    
+    pub output_mint: InterfaceAccount<'info, Mint>,
+    
+    /// Router-owned fee account for the input mint
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"fee_vault", input_mint.key().as_ref()],
+        bump,
+        token::mint = input_mint,
+        token::authority = router_state,
+        token::token_program = token_program
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Per-mint volume stats for the input mint
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + MintStats::INIT_SPACE,
+        seeds = [b"mint_stats", input_mint.key().as_ref()],
+        bump
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    /// Optional per-user stats, updated when the user has opted in
+    #[account(
+        mut,
+        seeds = [b"user_stats", user.key().as_ref()],
+        bump = user_stats.bump
+    )]
+    pub user_stats: Option<Account<'info, UserSwapStats>>,
+    
+    /// Optional volume-tiered fee schedule for the input mint
+    #[account(
+        seeds = [b"fee_schedule", input_mint.key().as_ref()],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Option<Account<'info, FeeSchedule>>,
+    
+    /// Optional authority-granted fee rate for this user
+    #[account(
+        seeds = [b"fee_override", user.key().as_ref()],
+        bump = fee_override.bump
+    )]
+    pub fee_override: Option<Account<'info, FeeOverride>>,
+    
+    /// Optional registered referrer sharing the router fee
+    #[account(
+        seeds = [b"referrer", referrer.wallet.as_ref()],
+        bump = referrer.bump
+    )]
+    pub referrer: Option<Account<'info, Referrer>>,
+    
+    /// Router-owned referral fee account for the referrer and input mint
+    #[account(
+        mut,
+        seeds = [
+            b"referral_fees",
+            referrer.as_ref().ok_or(ErrorCode::InvalidReferrer)?.key().as_ref(),
+            input_mint.key().as_ref()
+        ],
+        bump,
+        token::mint = input_mint,
+        token::authority = router_state
+    )]
+    pub referral_fee_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(seeds = [b"dex_registry"], bump = dex_registry.bump)]
+    pub dex_registry: Account<'info, DexRegistry>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    
+    // Leg programs and their accounts are passed via remaining_accounts
+}
+
+impl<'info> ExecuteRoute<'info> {
+    pub fn fee_accounts(&self) -> RouterFeeAccounts<'_, 'info> {
+        RouterFeeAccounts {
+            user: &self.user,
+            user_source_token: &self.user_source_token,
+            input_mint: &self.input_mint,
+            fee_vault: &self.fee_vault,
+            fee_override: self.fee_override.as_ref(),
+            fee_schedule: self.fee_schedule.as_ref(),
+            user_stats: self.user_stats.as_ref(),
+            referrer: self.referrer.as_ref(),
+            referral_fee_vault: self.referral_fee_vault.as_ref(),
+            token_program: &self.token_program,
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdateRouter<'info> {
+    #[account(
+        mut,
+        seeds = [b"router_state"],
+        bump = router_state.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub router_state: Account<'info, RouterState>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"router_state"],
+        bump = router_state.bump,
+        constraint = router_state.pending_authority == pending_authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub router_state: Account<'info, RouterState>,
+    
+    pub pending_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawProtocolFees<'info> {
+    #[account(
+        seeds = [b"router_state"],
+        bump = router_state.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub router_state: Account<'info, RouterState>,
+    
+    pub authority: Signer<'info>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"fee_vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = router_state
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Must be owned by the configured treasury, if any
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = router_state.treasury == Pubkey::default()
+            || destination.owner == router_state.treasury @ ErrorCode::InvalidTreasury
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeDexRegistry<'info> {
+    #[account(
+        seeds = [b"router_state"],
+        bump = router_state.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub router_state: Account<'info, RouterState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + DexRegistry::INIT_SPACE,
+        seeds = [b"dex_registry"],
+        bump
+    )]
+    pub dex_registry: Account<'info, DexRegistry>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRouterMetrics<'info> {
+    #[account(
+        seeds = [b"router_state"],
+        bump = router_state.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub router_state: Account<'info, RouterState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RouterMetrics::INIT_SPACE,
+        seeds = [b"router_metrics"],
+        bump
+    )]
+    pub router_metrics: Account<'info, RouterMetrics>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateDexRegistry<'info> {
+    #[account(
+        seeds = [b"router_state"],
+        bump = router_state.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub router_state: Account<'info, RouterState>,
+    
+    #[account(
+        mut,
+        seeds = [b"dex_registry"],
+        bump = dex_registry.bump
+    )]
+    pub dex_registry: Account<'info, DexRegistry>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeSchedule<'info> {
+    #[account(
+        seeds = [b"router_state"],
+        bump = router_state.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub router_state: Account<'info, RouterState>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + FeeSchedule::INIT_SPACE,
+        seeds = [b"fee_schedule", mint.key().as_ref()],
+        bump
+    )]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct SetFeeOverride<'info> {
+    #[account(
+        seeds = [b"router_state"],
+        bump = router_state.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub router_state: Account<'info, RouterState>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + FeeOverride::INIT_SPACE,
+        seeds = [b"fee_override", wallet.as_ref()],
+        bump
+    )]
+    pub fee_override: Account<'info, FeeOverride>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFeeOverride<'info> {
+    #[account(
+        seeds = [b"router_state"],
+        bump = router_state.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub router_state: Account<'info, RouterState>,
+    
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"fee_override", fee_override.wallet.as_ref()],
+        bump = fee_override.bump
+    )]
+    pub fee_override: Account<'info, FeeOverride>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct RegisterReferrer<'info> {
+    #[account(
+        seeds = [b"router_state"],
+        bump = router_state.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub router_state: Account<'info, RouterState>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + Referrer::INIT_SPACE,
+        seeds = [b"referrer", wallet.as_ref()],
+        bump
+    )]
+    pub referrer: Account<'info, Referrer>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeReferralFeeVault<'info> {
+    #[account(seeds = [b"router_state"], bump = router_state.bump)]
+    pub router_state: Account<'info, RouterState>,
+    
+    #[account(seeds = [b"referrer", referrer.wallet.as_ref()], bump = referrer.bump)]
+    pub referrer: Account<'info, Referrer>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"referral_fees", referrer.key().as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = router_state,
+        token::token_program = token_program
+    )]
+    pub referral_fee_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralFees<'info> {
+    #[account(seeds = [b"router_state"], bump = router_state.bump)]
+    pub router_state: Account<'info, RouterState>,
     
-    #[msg("Empty instruction data")]
-    EmptyInstructionData,
+    #[account(
+        seeds = [b"referrer", wallet.key().as_ref()],
+        bump = referrer.bump,
+        has_one = wallet @ ErrorCode::Unauthorized
+    )]
+    pub referrer: Account<'info, Referrer>,
+    
+    pub wallet: Signer<'info>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"referral_fees", referrer.key().as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = router_state
+    )]
+    pub referral_fee_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = mint)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeUserStats<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + UserSwapStats::INIT_SPACE,
+        seeds = [b"user_stats", user.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Account<'info, UserSwapStats>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteConstantProduct<'info> {
+    #[account(seeds = [b"router_state"], bump = router_state.bump)]
+    pub router_state: Account<'info, RouterState>,
+    
+    /// CHECK: Token-swap pool state - owner and layout validated in instruction
+    pub pool: UncheckedAccount<'info>,
+    
+    /// Pool reserve for the input mint
+    pub reserve_in: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Pool reserve for the output mint
+    pub reserve_out: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Token-swap program owning the pool
+    pub swap_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetStats<'info> {
+    #[account(seeds = [b"router_state"], bump = router_state.bump)]
+    pub router_state: Account<'info, RouterState>,
+}
+
+#[derive(Accounts)]
+pub struct GetStatsWindow<'info> {
+    #[account(seeds = [b"router_metrics"], bump = router_metrics.bump)]
+    pub router_metrics: Account<'info, RouterMetrics>,
+}
+
+#[derive(Accounts)]
+pub struct GetUserStats<'info> {
+    #[account(seeds = [b"user_stats", user_stats.user.as_ref()], bump = user_stats.bump)]
+    pub user_stats: Account<'info, UserSwapStats>,
+}
+
+// ========== STATE ==========
+
+#[account]
+pub struct RouterState {
+    pub authority: Pubkey,      // 32
+    pub fee_rate_bps: u16,      // 2  (basis points, 100 = 1%)
+    pub total_swaps: u64,       // 8
+    pub total_volume: u64,      // 8
+    pub bump: u8,               // 1
+    pub pending_authority: Pubkey, // 32 (Pubkey::default() when no transfer is pending)
+    pub paused: bool,           // 1
+    pub treasury: Pubkey,       // 32 (Pubkey::default() when unrestricted)
+    pub version: u8,            // 1  (layout version, see migrate_router_state)
+    pub max_slot_volume: u64,   // 8  (circuit breaker cap per slot, 0 = no cap)
+    pub max_hourly_volume: u64, // 8  (circuit breaker cap per hour, 0 = no cap)
+    pub current_slot: u64,      // 8  (slot of the last swap)
+    pub slot_volume: u64,       // 8  (swap volume in current_slot)
+    pub reserved: [u8; 32],     // 32 (zeroed, room for future fields)
+}
+
+impl RouterState {
+    pub const CURRENT_VERSION: u8 = 1;
+    pub const RESERVED_LEN: usize = 32;
+    pub const INIT_SPACE: usize =
+        32 + 2 + 8 + 8 + 1 + 32 + 1 + 32 + 1 + 8 + 8 + 8 + 8 + Self::RESERVED_LEN;
     
-    #[msg("Unauthorized")]
-    Unauthorized,
-}
\ No newline at end of file
+    // v0 layout: discriminator + authority, fee_rate_bps, total_swaps, total_volume, bump
+    pub const V0_LEN: usize = 8 + 32 + 2 + 8 + 8 + 1;
+
+    /// Decode a v0 account, defaulting the fields added since
+    pub fn from_v0(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() == Self::V0_LEN && data.starts_with(Self::DISCRIMINATOR),
+            ErrorCode::InvalidRouterStateVersion
+        );
+        
+        Ok(Self {
+            authority: Pubkey::new_from_array(data[8..40].try_into().unwrap()),
+            fee_rate_bps: u16::from_le_bytes(data[40..42].try_into().unwrap()),
+            total_swaps: u64::from_le_bytes(data[42..50].try_into().unwrap()),
+            total_volume: u64::from_le_bytes(data[50..58].try_into().unwrap()),
+            bump: data[58],
+            pending_authority: Pubkey::default(),
+            paused: false,
+            treasury: Pubkey::default(),
+            version: Self::CURRENT_VERSION,
+            max_slot_volume: 0,
+            max_hourly_volume: 0,
+            current_slot: 0,
+            slot_volume: 0,
+            reserved: [0; Self::RESERVED_LEN],
+        })
+    }
+
+    pub fn config_changed(&self) -> RouterConfigChanged {
+        RouterConfigChanged {
+            authority: self.authority,
+            pending_authority: self.pending_authority,
+            fee_rate_bps: self.fee_rate_bps,
+            paused: self.paused,
+            treasury: self.treasury,
+            max_slot_volume: self.max_slot_volume,
+            max_hourly_volume: self.max_hourly_volume,
+        }
+    }
+
+    /// Whether swapping `amount` now would push the slot or hourly volume over its cap
+    pub fn exceeds_volume_caps(&self, slot: u64, amount: u64, hourly_volume: u64) -> Result<bool> {
+        let slot_volume = if self.current_slot == slot { self.slot_volume } else { 0 };
+        let slot_total = slot_volume.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        let hourly_total = hourly_volume.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        
+        Ok((self.max_slot_volume > 0 && slot_total > self.max_slot_volume)
+            || (self.max_hourly_volume > 0 && hourly_total > self.max_hourly_volume))
+    }
+
+    /// Pause the router and describe why
+    pub fn trip_circuit_breaker(&mut self, slot: u64, amount: u64, hourly_volume: u64) -> CircuitBreakerTripped {
+        self.paused = true;
+        CircuitBreakerTripped {
+            slot,
+            amount,
+            slot_volume: if self.current_slot == slot { self.slot_volume } else { 0 },
+            hourly_volume,
+            max_slot_volume: self.max_slot_volume,
+            max_hourly_volume: self.max_hourly_volume,
+        }
+    }
+
+    /// Record a completed swap's measured volume in the totals, the current slot and
+    /// the hourly metrics. If it pushed the slot or hourly volume over a cap, the
+    /// router pauses so later swaps are rejected; the swap itself stands.
+    pub fn record_swap(
+        &mut self,
+        metrics: &mut RouterMetrics,
+        clock: &Clock,
+        volume: u64,
+        fee: u64,
+    ) -> Result<()> {
+        self.total_swaps = self.total_swaps.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        self.total_volume = self.total_volume.checked_add(volume).ok_or(ErrorCode::MathOverflow)?;
+        
+        let hourly_volume = metrics.hour_volume(clock.unix_timestamp);
+        if self.exceeds_volume_caps(clock.slot, volume, hourly_volume)? {
+            emit!(self.trip_circuit_breaker(clock.slot, volume, hourly_volume));
+            emit!(self.config_changed());
+            
+            msg!(" Circuit breaker tripped, router paused");
+        }
+        
+        self.record_slot_volume(clock.slot, volume)?;
+        metrics.record(clock.unix_timestamp, volume, fee)
+    }
+
+    /// Add a swap to the current slot's volume
+    pub fn record_slot_volume(&mut self, slot: u64, volume: u64) -> Result<()> {
+        if self.current_slot != slot {
+            self.current_slot = slot;
+            self.slot_volume = 0;
+        }
+        self.slot_volume = self.slot_volume.checked_add(volume).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[account]
+pub struct MintStats {
+    pub mint: Pubkey,           // 32
+    pub total_volume: u64,      // 8  (input volume routed in this mint)
+    pub bump: u8,               // 1
+}
+
+impl MintStats {
+    pub const INIT_SPACE: usize = 32 + 8 + 1;
+}
+
+/// Swaps routed during one hour (`hour` = unix_timestamp / 3600)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HourlyBucket {
+    pub hour: i64,              // 8
+    pub swap_count: u64,        // 8
+    pub volume: u64,            // 8
+    pub fees: u64,              // 8
+}
+
+/// Ring buffer of the last 7 days of hourly swap metrics
+#[account]
+pub struct RouterMetrics {
+    pub bump: u8,                       // 1
+    pub buckets: Vec<HourlyBucket>,     // 4 + BUCKETS * 32
+}
+
+impl RouterMetrics {
+    pub const SECONDS_PER_HOUR: i64 = 3600;
+    pub const BUCKETS: usize = 24 * 7;
+    pub const INIT_SPACE: usize = 1 + 4 + Self::BUCKETS * (8 + 8 + 8 + 8);
+
+    fn hour(now: i64) -> i64 {
+        now.div_euclid(Self::SECONDS_PER_HOUR)
+    }
+
+    /// Add a swap to the bucket for the current hour, recycling the slot if it is stale
+    pub fn record(&mut self, now: i64, volume: u64, fee: u64) -> Result<()> {
+        let hour = Self::hour(now);
+        let index = hour.rem_euclid(Self::BUCKETS as i64) as usize;
+        let bucket = self.buckets.get_mut(index).ok_or(ErrorCode::MathOverflow)?;
+        
+        if bucket.hour != hour {
+            *bucket = HourlyBucket { hour, ..HourlyBucket::default() };
+        }
+        
+        bucket.swap_count = bucket.swap_count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        bucket.volume = bucket.volume.checked_add(volume).ok_or(ErrorCode::MathOverflow)?;
+        bucket.fees = bucket.fees.checked_add(fee).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Volume recorded so far in the current hour
+    pub fn hour_volume(&self, now: i64) -> u64 {
+        let hour = Self::hour(now);
+        let index = hour.rem_euclid(Self::BUCKETS as i64) as usize;
+        self.buckets
+            .get(index)
+            .filter(|bucket| bucket.hour == hour)
+            .map_or(0, |bucket| bucket.volume)
+    }
+
+    /// Aggregate the buckets covering the last `hours` hours, including the current one
+    pub fn window(&self, now: i64, hours: u16) -> Result<StatsWindow> {
+        let current_hour = Self::hour(now);
+        let first_hour = current_hour - hours as i64 + 1;
+        
+        let mut stats = StatsWindow {
+            hours,
+            window_start: first_hour * Self::SECONDS_PER_HOUR,
+            swap_count: 0,
+            volume: 0,
+            fees: 0,
+        };
+        for bucket in self.buckets.iter().filter(|b| b.hour >= first_hour && b.hour <= current_hour) {
+            stats.swap_count = stats.swap_count.checked_add(bucket.swap_count).ok_or(ErrorCode::MathOverflow)?;
+            stats.volume = stats.volume.checked_add(bucket.volume).ok_or(ErrorCode::MathOverflow)?;
+            stats.fees = stats.fees.checked_add(bucket.fees).ok_or(ErrorCode::MathOverflow)?;
+        }
+        Ok(stats)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DexProgram {
+    pub program_id: Pubkey,     // 32
+    pub enabled: bool,          // 1
+}
+
+/// Allowlist of DEX/aggregator programs the router may CPI into
+#[account]
+pub struct DexRegistry {
+    pub bump: u8,                   // 1
+    pub programs: Vec<DexProgram>,  // 4 + MAX_PROGRAMS * 33
+}
+
+impl DexRegistry {
+    pub const MAX_PROGRAMS: usize = 16;
+    pub const INIT_SPACE: usize = 1 + 4 + Self::MAX_PROGRAMS * (32 + 1);
+
+    pub fn find(&self, program_id: &Pubkey) -> Option<usize> {
+        self.programs.iter().position(|p| p.program_id == *program_id)
+    }
+
+    pub fn is_enabled(&self, program_id: &Pubkey) -> bool {
+        self.programs
+            .iter()
+            .any(|p| p.program_id == *program_id && p.enabled)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct UserMintStats {
+    pub mint: Pubkey,           // 32
+    pub volume: u64,            // 8  (cumulative input volume)
+    pub fees_paid: u64,         // 8
+    pub window_start: i64,      // 8  (start of the current 30-day window)
+    pub window_volume: u64,     // 8  (input volume in the current window)
+    pub prev_window_volume: u64, // 8 (input volume in the previous window)
+}
+
+impl UserMintStats {
+    pub const VOLUME_WINDOW_SECS: i64 = 30 * 24 * 60 * 60;
+
+    /// Roll the 30-day window forward if it has elapsed
+    pub fn roll_window(&mut self, now: i64) {
+        let elapsed = now.saturating_sub(self.window_start);
+        if elapsed < Self::VOLUME_WINDOW_SECS {
+            return;
+        }
+        self.prev_window_volume = if elapsed < 2 * Self::VOLUME_WINDOW_SECS {
+            self.window_volume
+        } else {
+            0
+        };
+        self.window_start = now;
+        self.window_volume = 0;
+    }
+
+    /// Volume used for fee tiers: the current or previous 30-day window, whichever is higher
+    pub fn tier_volume(&self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.window_start);
+        if elapsed < Self::VOLUME_WINDOW_SECS {
+            self.window_volume.max(self.prev_window_volume)
+        } else if elapsed < 2 * Self::VOLUME_WINDOW_SECS {
+            self.window_volume
+        } else {
+            0
+        }
+    }
+}
+
+/// Opt-in per-user swap statistics
+#[account]
+pub struct UserSwapStats {
+    pub user: Pubkey,                   // 32
+    pub swap_count: u64,                // 8
+    pub last_swap_slot: u64,            // 8
+    pub bump: u8,                       // 1
+    pub mints: Vec<UserMintStats>,      // 4 + MAX_MINTS * 72
+}
+
+impl UserSwapStats {
+    pub const MAX_MINTS: usize = 10;
+    pub const INIT_SPACE: usize = 32 + 8 + 8 + 1 + 4 + Self::MAX_MINTS * (32 + 8 + 8 + 8 + 8 + 8);
+
+    pub fn find(&self, mint: &Pubkey) -> Option<&UserMintStats> {
+        self.mints.iter().find(|m| m.mint == *mint)
+    }
+
+    /// Record a swap; when all mint slots are used, the lowest-volume mint is evicted
+    pub fn record_swap(
+        &mut self,
+        mint: Pubkey,
+        volume: u64,
+        fee: u64,
+        slot: u64,
+        now: i64,
+    ) -> Result<()> {
+        self.swap_count = self.swap_count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        self.last_swap_slot = slot;
+        
+        let index = match self.mints.iter().position(|m| m.mint == mint) {
+            Some(index) => index,
+            None => {
+                if self.mints.len() >= Self::MAX_MINTS {
+                    let (lowest, _) = self.mints
+                        .iter()
+                        .enumerate()
+                        .min_by_key(|(_, m)| m.volume)
+                        .ok_or(ErrorCode::MathOverflow)?;
+                    self.mints.swap_remove(lowest);
+                }
+                self.mints.push(UserMintStats {
+                    mint,
+                    volume: 0,
+                    fees_paid: 0,
+                    window_start: now,
+                    window_volume: 0,
+                    prev_window_volume: 0,
+                });
+                self.mints.len() - 1
+            }
+        };
+        
+        let entry = &mut self.mints[index];
+        entry.roll_window(now);
+        entry.volume = entry.volume.checked_add(volume).ok_or(ErrorCode::MathOverflow)?;
+        entry.window_volume = entry.window_volume.checked_add(volume).ok_or(ErrorCode::MathOverflow)?;
+        entry.fees_paid = entry.fees_paid.checked_add(fee).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeTier {
+    pub min_volume: u64,        // 8  (30-day volume threshold, raw mint units)
+    pub fee_rate_bps: u16,      // 2
+}
+
+/// Volume-tiered fee rates for one input mint
+#[account]
+pub struct FeeSchedule {
+    pub mint: Pubkey,           // 32
+    pub bump: u8,               // 1
+    pub tiers: Vec<FeeTier>,    // 4 + MAX_TIERS * 10 (ascending min_volume)
+}
+
+impl FeeSchedule {
+    pub const MAX_TIERS: usize = 8;
+    pub const INIT_SPACE: usize = 32 + 1 + 4 + Self::MAX_TIERS * (8 + 2);
+
+    /// Fee rate of the highest tier reached by `volume`, if any
+    pub fn fee_rate_for(&self, volume: u64) -> Option<u16> {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|tier| volume >= tier.min_volume)
+            .map(|tier| tier.fee_rate_bps)
+    }
+}
+
+/// Integrator registered to receive a share of the router fee
+#[account]
+pub struct Referrer {
+    pub wallet: Pubkey,         // 32
+    pub share_bps: u16,         // 2  (share of the router fee, 10000 = 100%)
+    pub bump: u8,               // 1
+}
+
+impl Referrer {
+    pub const INIT_SPACE: usize = 32 + 2 + 1;
+}
+
+/// Authority-granted fee rate for a single wallet
+#[account]
+pub struct FeeOverride {
+    pub wallet: Pubkey,         // 32
+    pub fee_rate_bps: u16,      // 2
+    pub bump: u8,               // 1
+}
+
+impl FeeOverride {
+    pub const INIT_SPACE: usize = 32 + 2 + 1;
+}
+
+// ========== INSTRUCTION ARGS ==========
+
+/// One hop of an `execute_route` call
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct RouteLeg {
+    pub program_id: Pubkey,         // registered DEX/aggregator program
+    pub data: Vec<u8>,              // instruction data for the leg
+    pub accounts_start: u16,        // first remaining_accounts index of the leg
+    pub accounts_len: u16,          // number of remaining_accounts used by the leg
+    pub output_token_index: u16,    // remaining_accounts index of the leg's output token account
+    pub output_mint: Pubkey,        // expected mint of the leg's output
+}
+
+impl RouteLeg {
+    pub const MAX_LEGS: usize = 4;
+}
+
+// ========== RETURN TYPES ==========
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct RouterStats {
+    pub authority: Pubkey,
+    pub fee_rate_bps: u16,
+    pub total_swaps: u64,
+    pub total_volume: u64,
+    pub paused: bool,
+    pub version: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct StatsWindow {
+    pub hours: u16,
+    pub window_start: i64,
+    pub swap_count: u64,
+    pub volume: u64,
+    pub fees: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ConstantProductQuote {
+    pub amount_out: u64,
+    pub router_fee: u64,
+    pub pool_fee: u64,
+    pub price_impact_bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct UserStats {
+    pub user: Pubkey,
+    pub swap_count: u64,
+    pub last_swap_slot: u64,
+    pub mints: Vec<UserMintStats>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v0_router_data(authority: &Pubkey) -> Vec<u8> {
+        let mut data = RouterState::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(authority.as_ref());
+        data.extend_from_slice(&30u16.to_le_bytes());
+        data.extend_from_slice(&12u64.to_le_bytes());
+        data.extend_from_slice(&34_000u64.to_le_bytes());
+        data.push(253);
+        data
+    }
+
+    #[test]
+    fn migrates_v0_router_state() {
+        let authority = Pubkey::new_unique();
+        let data = v0_router_data(&authority);
+        assert_eq!(data.len(), RouterState::V0_LEN);
+
+        let router = RouterState::from_v0(&data).unwrap();
+        assert_eq!(router.authority, authority);
+        assert_eq!(router.fee_rate_bps, 30);
+        assert_eq!(router.total_swaps, 12);
+        assert_eq!(router.total_volume, 34_000);
+        assert_eq!(router.bump, 253);
+        assert_eq!(router.version, RouterState::CURRENT_VERSION);
+        assert_eq!(router.pending_authority, Pubkey::default());
+        assert!(!router.paused);
+        assert_eq!(router.treasury, Pubkey::default());
+        assert_eq!((router.max_slot_volume, router.max_hourly_volume), (0, 0));
+
+        // The rewritten account fills the current layout and reads back unchanged
+        let mut migrated = [0u8; 8 + RouterState::INIT_SPACE];
+        router.try_serialize(&mut &mut migrated[..]).unwrap();
+        let reloaded = RouterState::try_deserialize(&mut &migrated[..]).unwrap();
+        assert_eq!(reloaded.authority, authority);
+        assert_eq!(reloaded.total_volume, 34_000);
+        assert_eq!(reloaded.version, RouterState::CURRENT_VERSION);
+    }
+
+    fn router_with_caps(max_slot_volume: u64, max_hourly_volume: u64) -> (RouterState, RouterMetrics) {
+        let mut router = RouterState::from_v0(&v0_router_data(&Pubkey::new_unique())).unwrap();
+        router.total_swaps = 0;
+        router.total_volume = 0;
+        router.max_slot_volume = max_slot_volume;
+        router.max_hourly_volume = max_hourly_volume;
+        let metrics = RouterMetrics {
+            bump: 0,
+            buckets: vec![HourlyBucket::default(); RouterMetrics::BUCKETS],
+        };
+        (router, metrics)
+    }
+
+    fn clock(slot: u64, unix_timestamp: i64) -> Clock {
+        Clock { slot, unix_timestamp, ..Clock::default() }
+    }
+
+    #[test]
+    fn records_swaps_without_caps() {
+        let (mut router, mut metrics) = router_with_caps(0, 0);
+        router.record_swap(&mut metrics, &clock(10, 0), u64::MAX / 2, 5).unwrap();
+        router.record_swap(&mut metrics, &clock(10, 0), u64::MAX / 2, 5).unwrap();
+
+        assert!(!router.paused);
+        assert_eq!(router.total_swaps, 2);
+        assert_eq!(router.total_volume, u64::MAX - 1);
+        assert_eq!(metrics.hour_volume(0), u64::MAX - 1);
+        assert_eq!(metrics.window(0, 1).unwrap().fees, 10);
+    }
+
+    #[test]
+    fn trips_on_measured_slot_volume() {
+        let (mut router, mut metrics) = router_with_caps(1_000, 0);
+        router.record_swap(&mut metrics, &clock(10, 0), 600, 0).unwrap();
+        router.record_swap(&mut metrics, &clock(10, 0), 400, 0).unwrap();
+        assert!(!router.paused, "reaching the cap exactly does not trip");
+
+        router.record_swap(&mut metrics, &clock(10, 0), 1, 0).unwrap();
+        assert!(router.paused);
+        // The swap that crossed the cap is still recorded
+        assert_eq!(router.total_swaps, 3);
+        assert_eq!(router.slot_volume, 1_001);
+    }
+
+    #[test]
+    fn slot_volume_resets_each_slot() {
+        let (mut router, mut metrics) = router_with_caps(1_000, 0);
+        router.record_swap(&mut metrics, &clock(10, 0), 600, 0).unwrap();
+        router.record_swap(&mut metrics, &clock(11, 0), 600, 0).unwrap();
+
+        assert!(!router.paused);
+        assert_eq!((router.current_slot, router.slot_volume), (11, 600));
+    }
+
+    #[test]
+    fn trips_on_measured_hourly_volume() {
+        let (mut router, mut metrics) = router_with_caps(0, 1_000);
+        router.record_swap(&mut metrics, &clock(10, 0), 600, 0).unwrap();
+        router.record_swap(&mut metrics, &clock(20, 3_599), 600, 0).unwrap();
+        assert!(router.paused);
+
+        // The next hour starts from an empty bucket
+        let (mut router, mut metrics) = router_with_caps(0, 1_000);
+        router.record_swap(&mut metrics, &clock(10, 0), 600, 0).unwrap();
+        router.record_swap(&mut metrics, &clock(20, 3_600), 600, 0).unwrap();
+        assert!(!router.paused);
+    }
+
+    #[test]
+    fn checks_caps_against_current_volume() {
+        let (mut router, mut metrics) = router_with_caps(1_000, 5_000);
+        router.record_swap(&mut metrics, &clock(10, 0), 600, 0).unwrap();
+
+        assert!(!router.exceeds_volume_caps(10, 400, metrics.hour_volume(0)).unwrap());
+        assert!(router.exceeds_volume_caps(10, 401, metrics.hour_volume(0)).unwrap());
+        // A new slot only counts against the hourly cap
+        assert!(!router.exceeds_volume_caps(11, 1_000, metrics.hour_volume(0)).unwrap());
+        assert!(router.exceeds_volume_caps(11, 1_000, 4_001).unwrap());
+
+        let event = router.trip_circuit_breaker(10, 401, metrics.hour_volume(0));
+        assert!(router.paused);
+        assert_eq!((event.slot_volume, event.hourly_volume), (600, 600));
+    }
+
+    #[test]
+    fn rejects_non_v0_router_state() {
+        let data = v0_router_data(&Pubkey::new_unique());
+
+        let mut current = data.clone();
+        current.resize(8 + RouterState::INIT_SPACE, 0);
+        assert_eq!(
+            RouterState::from_v0(&current).err(),
+            Some(ErrorCode::InvalidRouterStateVersion.into())
+        );
+
+        let mut wrong_discriminator = data;
+        wrong_discriminator[0] ^= 1;
+        assert_eq!(
+            RouterState::from_v0(&wrong_discriminator).err(),
+            Some(ErrorCode::InvalidRouterStateVersion.into())
+        );
+    }
+}
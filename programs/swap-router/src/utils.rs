@@ -1,12 +1,24 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::AccountMeta;
+use anchor_spl::token::spl_token;
+use anchor_spl::token_interface::spl_token_2022::{
+    self,
+    extension::{
+        transfer_fee::TransferFeeConfig, BaseStateWithExtensions, ExtensionType,
+        StateWithExtensions,
+    },
+};
+use anchor_spl::token_interface::TokenAccount;
 use crate::errors::ErrorCode;
+use crate::router::{DexRegistry, FeeOverride, FeeSchedule, UserSwapStats, VAULT_PROGRAM};
 
 /// Calculate fee amount based on input amount and fee rate
 pub fn calculate_fee(amount: u64, fee_rate: u16) -> Result<u64> {
-    amount
-        .checked_mul(fee_rate as u64)
+    let fee = (amount as u128)
+        .checked_mul(fee_rate as u128)
         .and_then(|result| result.checked_div(10000))
-        .ok_or(ErrorCode::MathOverflow.into())
+        .ok_or(ErrorCode::MathOverflow)?;
+    u64::try_from(fee).map_err(|_| ErrorCode::MathOverflow.into())
 }
 
 /// Calculate net amount after fee deduction
@@ -17,6 +29,31 @@ pub fn calculate_net_amount(amount: u64, fee_rate: u16) -> Result<u64> {
         .ok_or(ErrorCode::MathOverflow.into())
 }
 
+/// Resolve the fee rate for a swap: wallet override, then volume tier, then base rate.
+/// Callers choose whether to pass the override and schedule, so neither can raise the
+/// rate above the base rate, even if the base rate was lowered after they were set.
+pub fn resolve_fee_rate(
+    base_fee_rate: u16,
+    fee_override: Option<&FeeOverride>,
+    fee_schedule: Option<&FeeSchedule>,
+    user_stats: Option<&UserSwapStats>,
+    input_mint: &Pubkey,
+    now: i64,
+) -> u16 {
+    if let Some(fee_override) = fee_override {
+        return fee_override.fee_rate_bps.min(base_fee_rate);
+    }
+    
+    let volume = user_stats
+        .and_then(|stats| stats.find(input_mint))
+        .map(|entry| entry.tier_volume(now))
+        .unwrap_or(0);
+    
+    fee_schedule
+        .and_then(|schedule| schedule.fee_rate_for(volume))
+        .map_or(base_fee_rate, |fee_rate| fee_rate.min(base_fee_rate))
+}
+
 /// Validate slippage tolerance
 pub fn validate_slippage(
     expected_amount: u64,
@@ -36,34 +73,221 @@ pub fn validate_slippage(
     Ok(())
 }
 
-/// Calculate price impact
+/// Calculate price impact against a constant-product (x * y = k) pool
+///
+/// Impact is the shortfall of `amount_out` versus the output at the pre-trade
+/// spot price `pool_balance_out / pool_balance_in`, in basis points. Pass the
+/// input net of pool fees so that fees are not counted as impact.
 pub fn calculate_price_impact(
     amount_in: u64,
     amount_out: u64,
     pool_balance_in: u64,
     pool_balance_out: u64,
 ) -> Result<u16> {
-    // Simplified price impact calculation
-    // Price impact = (amount_in / pool_balance_in) * 10000 (in basis points)
+    if amount_in == 0 || pool_balance_in == 0 || pool_balance_out == 0 {
+        return Ok(0);
+    }
+    
+    // Both products fit in u128 since each factor is a u64
+    let spot_out = (amount_in as u128) * (pool_balance_out as u128);
+    let actual_out = (amount_out as u128) * (pool_balance_in as u128);
     
-    if pool_balance_in == 0 {
+    if actual_out >= spot_out {
         return Ok(0);
     }
     
-    let impact = amount_in
-        .checked_mul(10000)
-        .and_then(|result| result.checked_div(pool_balance_in))
-        .ok_or(ErrorCode::MathOverflow)?;
+    let shortfall = spot_out - actual_out;
+    let impact = match shortfall.checked_mul(10000) {
+        Some(scaled) => scaled / spot_out,
+        None => shortfall / (spot_out / 10000),
+    };
     
     // Cap at 100% (10000 basis points)
-    Ok(std::cmp::min(impact as u16, 10000))
+    Ok(std::cmp::min(impact, 10000) as u16)
 }
 
-/// Validate DEX program
-pub fn validate_dex_program(program_id: &Pubkey, expected_program: &Pubkey) -> Result<()> {
+/// Validate DEX program against the registry allowlist
+pub fn validate_dex_program(registry: &DexRegistry, program_id: &Pubkey) -> Result<()> {
     require!(
-        program_id == expected_program,
+        registry.is_enabled(program_id),
         ErrorCode::InvalidDexProgram
     );
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Deserialize an SPL token account and check its mint and owner
+pub fn read_token_account(
+    account: &AccountInfo,
+    expected_mint: &Pubkey,
+    expected_owner: &Pubkey,
+) -> Result<TokenAccount> {
+    require!(
+        account.owner == &spl_token::ID || account.owner == &spl_token_2022::ID,
+        ErrorCode::InvalidRoute
+    );
+    
+    let token_account = TokenAccount::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    require!(token_account.mint == *expected_mint, ErrorCode::TokenMintMismatch);
+    require!(token_account.owner == *expected_owner, ErrorCode::InvalidRoute);
+    
+    Ok(token_account)
+}
+
+/// Maximum number of remaining accounts forwarded into router CPIs
+pub const MAX_REMAINING_ACCOUNTS: usize = 64;
+
+/// Build CPI account metas from `remaining_accounts`, rejecting:
+/// - more than `MAX_REMAINING_ACCOUNTS` accounts
+/// - writable accounts owned by the router or vault programs
+/// - writable token accounts whose authority is the router state (router fee accounts)
+/// - signers other than `allowed_signer` (the user or vault authority)
+pub fn sanitize_remaining_accounts(
+    accounts: &[AccountInfo],
+    allowed_signer: &Pubkey,
+    router_state: &Pubkey,
+) -> Result<Vec<AccountMeta>> {
+    require!(
+        accounts.len() <= MAX_REMAINING_ACCOUNTS,
+        ErrorCode::TooManyAccounts
+    );
+    
+    accounts
+        .iter()
+        .map(|acc| {
+            if acc.is_writable {
+                require!(
+                    acc.owner != &crate::ID && acc.owner != &VAULT_PROGRAM,
+                    ErrorCode::ProtectedAccountWritable
+                );
+                
+                if acc.owner == &spl_token::ID || acc.owner == &spl_token_2022::ID {
+                    let data = acc.try_borrow_data()?;
+                    if let Ok(token_account) = TokenAccount::try_deserialize(&mut &data[..]) {
+                        require!(
+                            token_account.owner != *router_state,
+                            ErrorCode::ProtectedAccountWritable
+                        );
+                    }
+                }
+            }
+            
+            require!(
+                !acc.is_signer || acc.key == allowed_signer,
+                ErrorCode::UnauthorizedSigner
+            );
+            
+            Ok(AccountMeta {
+                pubkey: acc.key(),
+                is_signer: acc.is_signer,
+                is_writable: acc.is_writable,
+            })
+        })
+        .collect()
+}
+
+/// Token-2022 mint extensions the router refuses to route
+pub const UNSUPPORTED_MINT_EXTENSIONS: [ExtensionType; 4] = [
+    ExtensionType::NonTransferable,
+    ExtensionType::PermanentDelegate,
+    ExtensionType::TransferHook,
+    ExtensionType::Pausable,
+];
+
+/// Whether a Token-2022 mint carries any of `UNSUPPORTED_MINT_EXTENSIONS`
+/// (always false for SPL Token mints)
+pub fn has_unsupported_mint_extension(mint: &AccountInfo) -> Result<bool> {
+    if mint.owner != &spl_token_2022::ID {
+        return Ok(false);
+    }
+    
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    let extensions = state.get_extension_types()?;
+    
+    Ok(extensions.iter().any(|ext| UNSUPPORTED_MINT_EXTENSIONS.contains(ext)))
+}
+
+/// Reject Token-2022 mints carrying unsupported extensions
+pub fn validate_mint_extensions(mint: &AccountInfo) -> Result<()> {
+    require!(
+        !has_unsupported_mint_extension(mint)?,
+        ErrorCode::UnsupportedMintExtension
+    );
+    Ok(())
+}
+
+/// Token-2022 transfer fee withheld when moving `amount` of `mint` (0 for SPL Token)
+pub fn transfer_fee_amount(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    if mint.owner != &spl_token_2022::ID {
+        return Ok(0);
+    }
+    
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    let fee = match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(ErrorCode::MathOverflow)?,
+        Err(_) => 0,
+    };
+    Ok(fee)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::FeeTier;
+
+    #[test]
+    fn fee_overrides_and_tiers_only_discount() {
+        let mint = Pubkey::new_unique();
+        let fee_override = |fee_rate_bps| FeeOverride { wallet: Pubkey::new_unique(), fee_rate_bps, bump: 0 };
+        let fee_schedule = FeeSchedule {
+            mint,
+            bump: 0,
+            tiers: vec![FeeTier { min_volume: 0, fee_rate_bps: 20 }],
+        };
+
+        assert_eq!(resolve_fee_rate(30, None, None, None, &mint, 0), 30);
+        assert_eq!(resolve_fee_rate(30, Some(&fee_override(10)), Some(&fee_schedule), None, &mint, 0), 10);
+        assert_eq!(resolve_fee_rate(30, None, Some(&fee_schedule), None, &mint, 0), 20);
+
+        // A base rate lowered below an existing override or tier caps them
+        assert_eq!(resolve_fee_rate(15, Some(&fee_override(25)), None, None, &mint, 0), 15);
+        assert_eq!(resolve_fee_rate(15, None, Some(&fee_schedule), None, &mint, 0), 15);
+    }
+
+    #[test]
+    fn price_impact_is_shortfall_against_spot_price() {
+        // Spot price 1:1 quotes 100 out; receiving 90 is a 10% shortfall
+        assert_eq!(calculate_price_impact(100, 90, 1_000, 1_000).unwrap(), 1_000);
+        // Spot price 1:2 quotes 200 out
+        assert_eq!(calculate_price_impact(100, 190, 1_000, 2_000).unwrap(), 500);
+        assert_eq!(calculate_price_impact(1_000, 500, 1_000, 1_000).unwrap(), 5_000);
+    }
+
+    #[test]
+    fn price_impact_floors_and_caps() {
+        // Output at or above spot has no impact
+        assert_eq!(calculate_price_impact(100, 100, 1_000, 1_000).unwrap(), 0);
+        assert_eq!(calculate_price_impact(100, 150, 1_000, 1_000).unwrap(), 0);
+        // No output is 100% impact
+        assert_eq!(calculate_price_impact(100, 0, 1_000, 1_000).unwrap(), 10_000);
+        // Sub-basis-point shortfalls round down
+        assert_eq!(calculate_price_impact(1_000_000, 999_999, 1_000, 1_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn price_impact_handles_empty_inputs() {
+        assert_eq!(calculate_price_impact(0, 0, 1_000, 1_000).unwrap(), 0);
+        assert_eq!(calculate_price_impact(100, 90, 0, 1_000).unwrap(), 0);
+        assert_eq!(calculate_price_impact(100, 90, 1_000, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn price_impact_survives_full_range_reserves() {
+        // shortfall * 10000 overflows u128 here, so the scaled-down division is used
+        let impact = calculate_price_impact(u64::MAX, u64::MAX / 2, u64::MAX, u64::MAX).unwrap();
+        assert_eq!(impact, 5_000);
+    }
+}
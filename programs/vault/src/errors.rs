@@ -14,10 +14,22 @@ pub enum ErrorCode {
     InvalidAuthority,
     #[msg("Slippage tolerance exceeded")]
     SlippageExceeded,
-    #[msg("Invalid token mint - only SOL/WSOL supported")]
+    #[msg("Token mint does not match the vault mint")]
     InvalidTokenMint,
     #[msg("Insufficient vault balance")]
     InsufficientVaultBalance,
     #[msg("Invalid minimum profit requirement")]
     InvalidMinProfit,
+    #[msg("Mint has an unsupported Token-2022 extension")]
+    UnsupportedMintExtension,
+    #[msg("Token account is not the vault's token account")]
+    InvalidVaultToken,
+    #[msg("Mint is not the vault's share mint")]
+    InvalidShareMint,
+    #[msg("Vault account is not at a migratable layout")]
+    InvalidVaultVersion,
+    #[msg("Deposit is too small to mint any shares")]
+    ZeroShares,
+    #[msg("Withdrawal must burn at least one share")]
+    ZeroWithdrawal,
 }
@@ -2,6 +2,7 @@ use anchor_lang::prelude::*;
 
 #[event]
 pub struct ArbitrageExecuted {
+    pub vault: Pubkey,
     pub executor: Pubkey,
     pub profit: u64,
     pub executor_fee: u64,
@@ -10,6 +11,7 @@ pub struct ArbitrageExecuted {
 
 #[event]
 pub struct Deposited {
+    pub vault: Pubkey,
     pub user: Pubkey,
     pub amount: u64,
     pub shares: u64,
@@ -17,7 +19,23 @@ pub struct Deposited {
 
 #[event]
 pub struct Withdrawn {
+    pub vault: Pubkey,
     pub user: Pubkey,
     pub amount: u64,
     pub shares: u64,
 }
+
+#[event]
+pub struct VaultV0Migrated {
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub v0_shares: u64,
+    pub shares: u64,
+}
+
+#[event]
+pub struct PositionMigrated {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub shares: u64,
+}
@@ -2,8 +2,10 @@ pub mod vault;
 pub mod state;
 pub mod events;
 pub mod errors;
+pub mod utils;
 
 pub use vault::*;
 pub use state::*;
 pub use events::*;
-pub use errors::*;
\ No newline at end of file
+pub use errors::*;
+pub use utils::*;
\ No newline at end of file
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::errors::ErrorCode;
 
 #[account]
 pub struct Vault {
@@ -6,18 +7,120 @@ pub struct Vault {
     pub swap_router: Pubkey,
     pub total_shares: u64,
     pub bump: u8,
+    pub vault_token: Pubkey,
+    pub mint: Pubkey,
+    pub share_mint: Pubkey,
 }
 
+/// Position in the v0 vault at `["position", user]`, claimed with `migrate_position_v0`
 #[account]
 pub struct UserPosition {
     pub owner: Pubkey,
     pub shares: u64,
 }
 
+/// Original single vault at `["vault"]`, from before vaults were keyed by mint.
+/// Its balance moves into a mint-keyed vault with `migrate_vault_v0`.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct VaultV0 {
+    pub authority: Pubkey,
+    pub swap_router: Pubkey,
+    pub total_shares: u64,
+    pub bump: u8,
+}
+
+/// Shares a v0 vault was converted into, held back until its positions claim them
+#[account]
+pub struct VaultV0Migration {
+    pub vault: Pubkey,
+    /// v0 shares not yet claimed
+    pub v0_shares: u64,
+    /// Shares of `vault` not yet minted to claimants
+    pub shares: u64,
+    pub bump: u8,
+}
+
 impl Vault {
-    pub const LEN: usize = 8 + 32 + 32 + 8 + 1;
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1 + 32 + 32 + 32;
 }
 
 impl UserPosition {
     pub const LEN: usize = 8 + 32 + 8;
 }
+
+impl VaultV0 {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1;
+
+    /// Decode a v0 vault account, which carries the current `Vault` discriminator
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() == Self::LEN && data.starts_with(Vault::DISCRIMINATOR),
+            ErrorCode::InvalidVaultVersion
+        );
+        Self::deserialize(&mut &data[8..]).map_err(|_| ErrorCode::InvalidVaultVersion.into())
+    }
+}
+
+impl VaultV0Migration {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault_v0_data(authority: Pubkey, swap_router: Pubkey, total_shares: u64, bump: u8) -> Vec<u8> {
+        let mut data = Vault::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(authority.as_ref());
+        data.extend_from_slice(swap_router.as_ref());
+        data.extend_from_slice(&total_shares.to_le_bytes());
+        data.push(bump);
+        data
+    }
+
+    #[test]
+    fn decodes_v0_vault() {
+        let authority = Pubkey::new_unique();
+        let swap_router = Pubkey::new_unique();
+        let data = vault_v0_data(authority, swap_router, 5_000, 254);
+        assert_eq!(data.len(), VaultV0::LEN);
+
+        let vault = VaultV0::decode(&data).unwrap();
+        assert_eq!(vault.authority, authority);
+        assert_eq!(vault.swap_router, swap_router);
+        assert_eq!(vault.total_shares, 5_000);
+        assert_eq!(vault.bump, 254);
+    }
+
+    #[test]
+    fn rejects_non_v0_vault() {
+        let mut data = vault_v0_data(Pubkey::new_unique(), Pubkey::new_unique(), 1, 255);
+
+        // Mint-keyed vaults are longer
+        let mut current = data.clone();
+        current.resize(Vault::LEN, 0);
+        assert_eq!(
+            VaultV0::decode(&current).err(),
+            Some(ErrorCode::InvalidVaultVersion.into())
+        );
+
+        data[..8].copy_from_slice(UserPosition::DISCRIMINATOR);
+        assert_eq!(
+            VaultV0::decode(&data).err(),
+            Some(ErrorCode::InvalidVaultVersion.into())
+        );
+    }
+
+    #[test]
+    fn decodes_v0_position() {
+        let owner = Pubkey::new_unique();
+        let mut data = UserPosition::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(owner.as_ref());
+        data.extend_from_slice(&1_234u64.to_le_bytes());
+        assert_eq!(data.len(), UserPosition::LEN);
+
+        let position = UserPosition::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(position.owner, owner);
+        assert_eq!(position.shares, 1_234);
+    }
+}
@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+use swap_router::{has_unsupported_mint_extension, DexRegistry};
+use crate::errors::ErrorCode;
+
+/// Reject Token-2022 mints the swap router refuses to route, so the vault
+/// never holds an asset its arbitrage cannot swap
+pub fn validate_mint_extensions(mint: &AccountInfo) -> Result<()> {
+    require!(
+        !has_unsupported_mint_extension(mint)?,
+        ErrorCode::UnsupportedMintExtension
+    );
+    Ok(())
+}
+
+/// Address of a swap router PDA with a single static seed
+pub fn router_pda(seed: &[u8], swap_router: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seed], swap_router).0
+}
+
+/// Require `program_id` to be enabled in the swap router's DEX registry
+pub fn validate_router_program(dex_registry: &AccountInfo, program_id: &Pubkey) -> Result<()> {
+    let registry = DexRegistry::try_deserialize(&mut &dex_registry.try_borrow_data()?[..])
+        .map_err(|_| ErrorCode::InvalidSwapRouter)?;
+
+    require!(registry.is_enabled(program_id), ErrorCode::InvalidSwapRouter);
+    Ok(())
+}
+
+// Virtual offsets added to both sides of the share price, so an empty vault still
+// has a price and a donation cannot divide by zero shares
+pub const VIRTUAL_SHARES: u64 = 1;
+pub const VIRTUAL_ASSETS: u64 = 1;
+
+// Shares the first deposit leaves locked in the vault. The supply never drops below
+// them, so inflating the price with a donation costs the donor ~1000x what it takes
+// from the next depositor. Shares keep the asset's decimals, one share per unit.
+pub const LOCKED_SHARES: u64 = 1_000;
+
+/// Shares the depositor receives out of `shares` minted into a vault with
+/// `total_shares` outstanding; the first deposit gives up `LOCKED_SHARES`
+pub fn lock_first_deposit(shares: u64, total_shares: u64) -> Result<u64> {
+    if total_shares > 0 {
+        return Ok(shares);
+    }
+    require!(shares > LOCKED_SHARES, ErrorCode::ZeroShares);
+    Ok(shares - LOCKED_SHARES)
+}
+
+/// Shares minted for `amount` deposited into a vault holding `total_assets`
+pub fn shares_for_deposit(amount: u64, total_shares: u64, total_assets: u64) -> Result<u64> {
+    mul_div(
+        amount,
+        total_shares as u128 + VIRTUAL_SHARES as u128,
+        total_assets as u128 + VIRTUAL_ASSETS as u128,
+    )
+}
+
+/// Assets paid out for burning `shares` of a vault holding `total_assets`
+pub fn assets_for_shares(shares: u64, total_shares: u64, total_assets: u64) -> Result<u64> {
+    mul_div(
+        shares,
+        total_assets as u128 + VIRTUAL_ASSETS as u128,
+        total_shares as u128 + VIRTUAL_SHARES as u128,
+    )
+}
+
+/// Shares owed to a v0 position of `shares`, out of the `migrated_shares` still
+/// held for the `v0_shares` not yet claimed. The last claimant receives the remainder.
+pub fn shares_for_v0_position(shares: u64, v0_shares: u64, migrated_shares: u64) -> Result<u64> {
+    require!(shares <= v0_shares, ErrorCode::InsufficientShares);
+    mul_div(shares, migrated_shares as u128, v0_shares as u128)
+}
+
+/// floor(value * numerator / denominator) with u128 intermediates
+fn mul_div(value: u64, numerator: u128, denominator: u128) -> Result<u64> {
+    let result = (value as u128)
+        .checked_mul(numerator)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(denominator)
+        .ok_or(ErrorCode::MathOverflow)?;
+    result.try_into().map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_deposit_locks_shares() {
+        let shares = shares_for_deposit(5_000, 0, 0).unwrap();
+        assert_eq!(shares, 5_000);
+        assert_eq!(lock_first_deposit(shares, 0).unwrap(), 5_000 - LOCKED_SHARES);
+        assert_eq!(lock_first_deposit(shares, 1).unwrap(), shares);
+        assert_eq!(
+            lock_first_deposit(LOCKED_SHARES, 0).err(),
+            Some(ErrorCode::ZeroShares.into())
+        );
+    }
+
+    #[test]
+    fn donation_does_not_grief_the_next_depositor() {
+        // The first depositor makes the smallest deposit allowed, keeps one share, then
+        // donates 1_000_000 to the vault token account
+        let first_shares = shares_for_deposit(LOCKED_SHARES + 1, 0, 0).unwrap();
+        let attacker_shares = lock_first_deposit(first_shares, 0).unwrap();
+        assert_eq!(attacker_shares, 1);
+        let assets = LOCKED_SHARES + 1 + 1_000_000;
+
+        let victim_shares = shares_for_deposit(1_000_000, first_shares, assets).unwrap();
+        let total_shares = first_shares + victim_shares;
+        let total_assets = assets + 1_000_000;
+
+        let victim_assets = assets_for_shares(victim_shares, total_shares, total_assets).unwrap();
+        assert!(victim_assets >= 999_000, "victim redeems {victim_assets}");
+
+        let attacker_assets = assets_for_shares(attacker_shares, total_shares, total_assets).unwrap();
+        assert!(attacker_assets < 1_000, "attacker redeems {attacker_assets}");
+    }
+
+    #[test]
+    fn splits_migrated_shares_across_v0_positions() {
+        // 100 v0 shares bought 299 shares: claims of 30 then 70 split them
+        let first = shares_for_v0_position(30, 100, 299).unwrap();
+        assert_eq!(first, 89);
+
+        let last = shares_for_v0_position(70, 100 - 30, 299 - first).unwrap();
+        assert_eq!(last, 299 - first);
+    }
+
+    #[test]
+    fn rejects_claims_over_outstanding_v0_shares() {
+        assert_eq!(
+            shares_for_v0_position(101, 100, 300).err(),
+            Some(ErrorCode::InsufficientShares.into())
+        );
+    }
+}
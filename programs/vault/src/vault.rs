@@ -1,64 +1,197 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
-use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{
+    self, Burn, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked,
+};
 use crate::state::*;
 use crate::events::*;
 use crate::errors::ErrorCode;
+use crate::utils::{
+    assets_for_shares, lock_first_deposit, router_pda, shares_for_deposit, shares_for_v0_position,
+    validate_mint_extensions, validate_router_program,
+};
 declare_id!("J9L1xWf6Krkg7284UThzykxNZ133Sw7Kk2fLHJ2cpKSn");
 
-// Wrapped SOL mint address
-pub const WSOL_MINT: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
-
+#[allow(clippy::module_inception)]
 #[program]
 pub mod vault {
     use super::*;
 
     pub fn initialize_vault(ctx: Context<InitializeVault>) -> Result<()> {
+        validate_mint_extensions(&ctx.accounts.mint.to_account_info())?;
+
         let vault = &mut ctx.accounts.vault;
         vault.authority = ctx.accounts.authority.key();
         vault.swap_router = ctx.accounts.swap_router.key();
         vault.total_shares = 0;
         vault.bump = ctx.bumps.vault;
+        vault.vault_token = ctx.accounts.vault_token.key();
+        vault.mint = ctx.accounts.mint.key();
+        vault.share_mint = ctx.accounts.share_mint.key();
         
         Ok(())
     }
 
-    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+    /// Move the v0 `["vault"]` vault's balance into the mint-keyed vault for the same
+    /// asset at that vault's current share price. The shares it buys are held in the
+    /// migration record until v0 positions claim them. Runs once, signed by the v0 authority.
+    pub fn migrate_vault_v0(ctx: Context<MigrateVaultV0>) -> Result<()> {
+        let vault_v0 = VaultV0::decode(&ctx.accounts.vault_v0.try_borrow_data()?)?;
+        require_keys_eq!(
+            vault_v0.authority,
+            ctx.accounts.authority.key(),
+            ErrorCode::InvalidAuthority
+        );
+
+        let vault_balance = ctx.accounts.vault_token.amount;
+        let amount = ctx.accounts.vault_v0_token.amount;
+
+        if amount > 0 {
+            let v0_bump = ctx.bumps.vault_v0;
+            let seeds = &[b"vault".as_ref(), &[v0_bump]];
+            let signer_seeds = &[&seeds[..]];
+
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.vault_v0_token.to_account_info(),
+                        mint: ctx.accounts.mint.to_account_info(),
+                        to: ctx.accounts.vault_token.to_account_info(),
+                        authority: ctx.accounts.vault_v0.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+                ctx.accounts.mint.decimals,
+            )?;
+        }
+
+        ctx.accounts.vault_token.reload()?;
+        let received = ctx.accounts.vault_token.amount.checked_sub(vault_balance)
+            .ok_or(ErrorCode::MathOverflow)?;
+
         let vault = &mut ctx.accounts.vault;
-        let user_position = &mut ctx.accounts.user_position;
+        let shares = shares_for_deposit(received, vault.total_shares, vault_balance)?;
+        // Migrating into an empty vault is its first deposit
+        let held_shares = if shares > 0 {
+            lock_first_deposit(shares, vault.total_shares)?
+        } else {
+            0
+        };
+        vault.total_shares = vault.total_shares.checked_add(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let migration = &mut ctx.accounts.migration;
+        migration.vault = vault.key();
+        migration.v0_shares = vault_v0.total_shares;
+        migration.shares = held_shares;
+        migration.bump = ctx.bumps.migration;
+
+        emit!(VaultV0Migrated {
+            vault: vault.key(),
+            amount: received,
+            v0_shares: vault_v0.total_shares,
+            shares: held_shares,
+        });
+
+        Ok(())
+    }
+
+    /// Claim a v0 `["position", user]` balance as share tokens of the vault the v0
+    /// vault migrated into, then close the position
+    pub fn migrate_position_v0(ctx: Context<MigratePositionV0>) -> Result<()> {
+        let position = &ctx.accounts.user_position;
+        let migration = &mut ctx.accounts.migration;
+        let shares = shares_for_v0_position(position.shares, migration.v0_shares, migration.shares)?;
+        migration.v0_shares = migration.v0_shares.checked_sub(position.shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+        migration.shares = migration.shares.checked_sub(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let vault = &ctx.accounts.vault;
+        let vault_bump = vault.bump;
+        let seeds = &[b"vault".as_ref(), vault.mint.as_ref(), &[vault_bump]];
+        let signer_seeds = &[&seeds[..]];
 
-        token::transfer(
+        if shares > 0 {
+            token_interface::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: ctx.accounts.share_mint.to_account_info(),
+                        to: ctx.accounts.user_share_token.to_account_info(),
+                        authority: vault.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                shares,
+            )?;
+        }
+
+        emit!(PositionMigrated {
+            vault: vault.key(),
+            user: ctx.accounts.user.key(),
+            shares,
+        });
+
+        Ok(())
+    }
+
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        validate_mint_extensions(&ctx.accounts.mint.to_account_info())?;
+
+        let vault_balance = ctx.accounts.vault_token.amount;
+
+        token_interface::transfer_checked(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
-                Transfer {
+                TransferChecked {
                     from: ctx.accounts.user_token.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
                     to: ctx.accounts.vault_token.to_account_info(),
                     authority: ctx.accounts.user.to_account_info(),
                 },
             ),
             amount,
+            ctx.accounts.mint.decimals,
         )?;
 
-        let shares = if vault.total_shares == 0 {
-            amount
-        } else {
-            let vault_balance = ctx.accounts.vault_token.amount;
-            amount.checked_mul(vault.total_shares)
-                .ok_or(ErrorCode::MathOverflow)?
-                .checked_div(vault_balance)
-                .ok_or(ErrorCode::MathOverflow)?
-        };
-
-        user_position.shares = user_position.shares.checked_add(shares)
+        // Shares are priced on what the vault actually received (net of Token-2022 transfer fees)
+        ctx.accounts.vault_token.reload()?;
+        let received = ctx.accounts.vault_token.amount.checked_sub(vault_balance)
             .ok_or(ErrorCode::MathOverflow)?;
-        user_position.owner = ctx.accounts.user.key();
+
+        let vault = &mut ctx.accounts.vault;
+
+        let shares = shares_for_deposit(received, vault.total_shares, vault_balance)?;
+        let user_shares = lock_first_deposit(shares, vault.total_shares)?;
+        require!(user_shares > 0, ErrorCode::ZeroShares);
+
         vault.total_shares = vault.total_shares.checked_add(shares)
             .ok_or(ErrorCode::MathOverflow)?;
 
+        let vault_bump = vault.bump;
+        let seeds = &[b"vault".as_ref(), vault.mint.as_ref(), &[vault_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    to: ctx.accounts.user_share_token.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            user_shares,
+        )?;
+
         emit!(Deposited {
+            vault: vault.key(),
             user: ctx.accounts.user.key(),
-            amount,
-            shares,
+            amount: received,
+            shares: user_shares,
         });
 
         Ok(())
@@ -67,19 +200,16 @@ pub mod vault {
     pub fn execute_arbitrage<'info>(
         ctx: Context<'_, '_, '_, 'info, ExecuteArbitrage<'info>>,
         jupiter_instruction_data: Vec<u8>,
+        amount_in: u64,
         min_profit: u64,
     ) -> Result<()> {
         let vault = &ctx.accounts.vault;
 
-        // Validate that this is a SOL-based arbitrage
-        require!(
-            ctx.accounts.vault_token.mint == WSOL_MINT,
-            ErrorCode::InvalidTokenMint
-        );
-        require!(
-            ctx.accounts.executor_token.mint == WSOL_MINT,
-            ErrorCode::InvalidTokenMint
-        );
+        validate_mint_extensions(&ctx.accounts.mint.to_account_info())?;
+        validate_router_program(
+            &ctx.accounts.dex_registry.to_account_info(),
+            &ctx.accounts.jupiter_program.key(),
+        )?;
         
         // Ensure vault has sufficient balance for arbitrage
         let initial_balance = ctx.accounts.vault_token.amount;
@@ -89,15 +219,22 @@ pub mod vault {
         require!(min_profit > 0, ErrorCode::InvalidMinProfit);
 
         let vault_bump = vault.bump;
-        let vault_seeds_data = vec![b"vault".to_vec(), vec![vault_bump]];
-        let seeds_slice: Vec<&[u8]> = vault_seeds_data.iter().map(|s| s.as_slice()).collect();
-        let signer_seeds = &[seeds_slice.as_slice()];
+        let seeds = &[b"vault".as_ref(), vault.mint.as_ref(), &[vault_bump]];
+        let signer_seeds = &[&seeds[..]];
 
         let cpi_program = ctx.accounts.swap_router_program.to_account_info();
         let cpi_accounts = swap_router::cpi::accounts::ExecuteVaultJupiterSwap {
             router_state: ctx.accounts.router_state.to_account_info(),
+            router_metrics: ctx.accounts.router_metrics.to_account_info(),
             vault_authority: vault.to_account_info(),
+            source_token: ctx.accounts.vault_token.to_account_info(),
+            destination_token: ctx.accounts.vault_token.to_account_info(),
+            output_mint: ctx.accounts.mint.to_account_info(),
+            mint_stats: ctx.accounts.mint_stats.to_account_info(),
+            payer: ctx.accounts.executor.to_account_info(),
+            dex_registry: ctx.accounts.dex_registry.to_account_info(),
             jupiter_program: ctx.accounts.jupiter_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
         };
 
         let cpi_ctx = CpiContext::new_with_signer(
@@ -109,7 +246,7 @@ pub mod vault {
         swap_router::cpi::execute_vault_jupiter_swap(
             cpi_ctx,
             jupiter_instruction_data,
-            vault_seeds_data.clone(),
+            amount_in,
         )?;
 
         ctx.accounts.vault_token.reload()?;
@@ -129,23 +266,23 @@ pub mod vault {
             .and_then(|v| v.checked_div(100))
             .ok_or(ErrorCode::MathOverflow)?;
 
-        let seeds_ref: Vec<&[u8]> = vault_seeds_data.iter().map(|s| s.as_slice()).collect();
-        let signer = &[seeds_ref.as_slice()];
-
-        token::transfer(
+        token_interface::transfer_checked(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
-                Transfer {
+                TransferChecked {
                     from: ctx.accounts.vault_token.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
                     to: ctx.accounts.executor_token.to_account_info(),
                     authority: vault.to_account_info(),
                 },
-                signer,
+                signer_seeds,
             ),
             executor_fee,
+            ctx.accounts.mint.decimals,
         )?;
 
         emit!(ArbitrageExecuted {
+            vault: vault.key(),
             executor: ctx.accounts.executor.key(),
             profit,
             executor_fee,
@@ -156,49 +293,59 @@ pub mod vault {
     }
 
     pub fn withdraw(ctx: Context<Withdraw>, shares: u64) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
-        let user_position = &mut ctx.accounts.user_position;
+        // With no shares outstanding, burning zero would otherwise match total_shares
+        // and pay out the whole balance
+        require!(shares > 0, ErrorCode::ZeroWithdrawal);
+        require!(
+            ctx.accounts.user_share_token.amount >= shares,
+            ErrorCode::InsufficientShares
+        );
 
-        require!(user_position.shares >= shares, ErrorCode::InsufficientShares);
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    from: ctx.accounts.user_share_token.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            shares,
+        )?;
 
+        let vault = &mut ctx.accounts.vault;
         let vault_balance = ctx.accounts.vault_token.amount;
 
         let amount = if shares == vault.total_shares {
             vault_balance
         } else {
-            let numerator = (shares as u128)
-                .checked_mul(vault_balance as u128)
-                .ok_or(ErrorCode::MathOverflow)?;
-            let amount_u128 = numerator
-                .checked_div(vault.total_shares as u128)
-                .ok_or(ErrorCode::MathOverflow)?;
-            amount_u128.try_into()
-                .map_err(|_| ErrorCode::MathOverflow)?
+            assets_for_shares(shares, vault.total_shares, vault_balance)?
         };
 
         let vault_bump = vault.bump;
-        let seeds = &[b"vault".as_ref(), &[vault_bump]];
+        let seeds = &[b"vault".as_ref(), vault.mint.as_ref(), &[vault_bump]];
         let signer_seeds = &[&seeds[..]];
 
-        token::transfer(
+        token_interface::transfer_checked(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
-                Transfer {
+                TransferChecked {
                     from: ctx.accounts.vault_token.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
                     to: ctx.accounts.user_token.to_account_info(),
                     authority: vault.to_account_info(),
                 },
                 signer_seeds,
             ),
             amount,
+            ctx.accounts.mint.decimals,
         )?;
 
-        user_position.shares = user_position.shares.checked_sub(shares)
-            .ok_or(ErrorCode::MathOverflow)?;
         vault.total_shares = vault.total_shares.checked_sub(shares)
             .ok_or(ErrorCode::MathOverflow)?;
 
         emit!(Withdrawn {
+            vault: vault.key(),
             user: ctx.accounts.user.key(),
             amount,
             shares,
@@ -214,98 +361,275 @@ pub struct InitializeVault<'info> {
         init,
         payer = authority,
         space = Vault::LEN,
-        seeds = [b"vault"],
+        seeds = [b"vault", mint.key().as_ref()],
         bump
     )]
     pub vault: Account<'info, Vault>,
     
+    /// Vault-owned token account holding the deposited assets
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"vault_token", vault.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault,
+        token::token_program = token_program
+    )]
+    pub vault_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// Vault share token mint, minted on deposit and burned on withdraw
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"share_mint", vault.key().as_ref()],
+        bump,
+        mint::decimals = mint.decimals,
+        mint::authority = vault,
+        mint::token_program = token_program
+    )]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+    
     #[account(mut)]
     pub authority: Signer<'info>,
     
-    /// CHECK: The swap router program that this vault will use (verified through has_one)
+    /// CHECK: The swap router program this vault will route arbitrage through
+    #[account(address = swap_router::ID @ ErrorCode::InvalidSwapRouter)]
     pub swap_router: UncheckedAccount<'info>,
     
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 pub struct Deposit<'info> {
-    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    #[account(mut, seeds = [b"vault", vault.mint.as_ref()], bump = vault.bump)]
     pub vault: Account<'info, Vault>,
 
+    #[account(mut)]
+    pub user: Signer<'info>,
+
     #[account(
-        init_if_needed,
-        payer = user,
-        space = UserPosition::LEN,
-        seeds = [b"position", user.key().as_ref()],
-        bump
+        mut,
+        address = vault.share_mint @ ErrorCode::InvalidShareMint
     )]
-    pub user_position: Account<'info, UserPosition>,
+    pub share_mint: InterfaceAccount<'info, Mint>,
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = user
+    )]
+    pub user_share_token: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub user_token: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = mint)]
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub vault_token: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        address = vault.vault_token @ ErrorCode::InvalidVaultToken,
+        token::authority = vault
+    )]
+    pub vault_token: InterfaceAccount<'info, TokenAccount>,
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+    #[account(
+        address = vault.mint @ ErrorCode::InvalidTokenMint,
+        mint::token_program = token_program
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
 pub struct ExecuteArbitrage<'info> {
-    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    // Read-only: the vault PDA is forwarded to the router as a signer and must not be writable
+    #[account(seeds = [b"vault", vault.mint.as_ref()], bump = vault.bump)]
     pub vault: Account<'info, Vault>,
 
-    #[account(mut)]
-    pub vault_token: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        address = vault.vault_token @ ErrorCode::InvalidVaultToken,
+        token::authority = vault
+    )]
+    pub vault_token: InterfaceAccount<'info, TokenAccount>,
 
     #[account(mut)]
     pub executor: Signer<'info>,
 
-    #[account(mut)]
-    pub executor_token: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = mint)]
+    pub executor_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = vault.mint @ ErrorCode::InvalidTokenMint,
+        mint::token_program = token_program
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
 
     /// CHECK: Verified against vault.swap_router
+    #[account(
+        executable,
+        address = vault.swap_router @ ErrorCode::InvalidSwapRouter
+    )]
     pub swap_router_program: UncheckedAccount<'info>,
 
     /// CHECK: Router state PDA from swap router program
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = router_state.key() == router_pda(b"router_state", &vault.swap_router)
+            @ ErrorCode::InvalidSwapRouter
+    )]
     pub router_state: UncheckedAccount<'info>,
 
-    /// CHECK: Jupiter V6 program ID
+    /// CHECK: Hourly metrics PDA from swap router program
+    #[account(mut)]
+    pub router_metrics: UncheckedAccount<'info>,
+
+    /// CHECK: Per-mint stats PDA from swap router program
+    #[account(mut)]
+    pub mint_stats: UncheckedAccount<'info>,
+
+    /// CHECK: DEX registry PDA from swap router program
+    #[account(
+        owner = vault.swap_router @ ErrorCode::InvalidSwapRouter,
+        constraint = dex_registry.key() == router_pda(b"dex_registry", &vault.swap_router)
+            @ ErrorCode::InvalidSwapRouter
+    )]
+    pub dex_registry: UncheckedAccount<'info>,
+
+    /// CHECK: Jupiter V6 program ID - must be enabled in the router's DEX registry
     pub jupiter_program: UncheckedAccount<'info>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
-    #[account(mut, seeds = [b"vault"], bump = vault.bump)]
+    #[account(mut, seeds = [b"vault", vault.mint.as_ref()], bump = vault.bump)]
     pub vault: Account<'info, Vault>,
 
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        address = vault.share_mint @ ErrorCode::InvalidShareMint
+    )]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = user
+    )]
+    pub user_share_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = vault.vault_token @ ErrorCode::InvalidVaultToken,
+        token::authority = vault
+    )]
+    pub vault_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = mint)]
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = vault.mint @ ErrorCode::InvalidTokenMint,
+        mint::token_program = token_program
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateVaultV0<'info> {
+    /// CHECK: v0 vault, decoded in the instruction
+    #[account(seeds = [b"vault"], bump, owner = crate::ID)]
+    pub vault_v0: UncheckedAccount<'info>,
+
+    /// Token account holding the v0 vault's deposits
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = vault_v0
+    )]
+    pub vault_v0_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"vault", vault.mint.as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        address = vault.vault_token @ ErrorCode::InvalidVaultToken,
+        token::authority = vault
+    )]
+    pub vault_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = vault.mint @ ErrorCode::InvalidTokenMint,
+        mint::token_program = token_program
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = VaultV0Migration::LEN,
+        seeds = [b"vault_v0_migration"],
+        bump
+    )]
+    pub migration: Account<'info, VaultV0Migration>,
+
+    /// Authority recorded in the v0 vault
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePositionV0<'info> {
+    #[account(seeds = [b"vault", vault.mint.as_ref()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_v0_migration"],
+        bump = migration.bump,
+        has_one = vault @ ErrorCode::InvalidVaultVersion
+    )]
+    pub migration: Account<'info, VaultV0Migration>,
+
     #[account(
         mut,
         seeds = [b"position", user.key().as_ref()],
         bump,
-        has_one = owner
+        constraint = user_position.owner == user.key() @ ErrorCode::InvalidAuthority,
+        close = user
     )]
     pub user_position: Account<'info, UserPosition>,
 
     #[account(mut)]
     pub user: Signer<'info>,
 
-    #[account(mut)]
-    pub vault_token: Account<'info, TokenAccount>,
-
-    #[account(mut)]
-    pub user_token: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        address = vault.share_mint @ ErrorCode::InvalidShareMint
+    )]
+    pub share_mint: InterfaceAccount<'info, Mint>,
 
-    pub token_program: Program<'info, Token>,
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = user
+    )]
+    pub user_share_token: InterfaceAccount<'info, TokenAccount>,
 
-    /// CHECK: Verified through has_one
-    pub owner: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
@@ -1,5 +1,6 @@
 const anchor = require("@coral-xyz/anchor");
 const { PublicKey, Keypair, SystemProgram } = anchor.web3;
+const { NATIVE_MINT, TOKEN_PROGRAM_ID } = require("@solana/spl-token");
 
 async function initialize() {
   // Configure the client
@@ -57,16 +58,95 @@ async function initialize() {
       console.log("Fee Rate:", feeRate, "basis points");
     }
 
+    // ========== INITIALIZE DEX REGISTRY ==========
+    console.log("\n Initializing DEX Registry...");
+
+    const [dexRegistryPda] = await PublicKey.findProgramAddress(
+      [Buffer.from("dex_registry")],
+      routerProgram.programId
+    );
+
+    console.log("DEX Registry PDA:", dexRegistryPda.toString());
+
+    let registryExists = false;
+    try {
+      await routerProgram.account.dexRegistry.fetch(dexRegistryPda);
+      registryExists = true;
+      console.log("  DEX registry already initialized, skipping...");
+    } catch (error) {
+      // Registry not initialized yet
+    }
+
+    if (!registryExists) {
+      const registryTx = await routerProgram.methods
+        .initializeDexRegistry()
+        .accounts({
+          routerState: routerStatePda,
+          dexRegistry: dexRegistryPda,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      console.log(" DEX registry initialized with Jupiter V6!");
+      console.log("Transaction signature:", registryTx);
+    }
+
+    // ========== INITIALIZE ROUTER METRICS ==========
+    console.log("\n Initializing Router Metrics...");
+
+    const [routerMetricsPda] = await PublicKey.findProgramAddress(
+      [Buffer.from("router_metrics")],
+      routerProgram.programId
+    );
+
+    console.log("Router Metrics PDA:", routerMetricsPda.toString());
+
+    let metricsExists = false;
+    try {
+      await routerProgram.account.routerMetrics.fetch(routerMetricsPda);
+      metricsExists = true;
+      console.log("  Router metrics already initialized, skipping...");
+    } catch (error) {
+      // Metrics not initialized yet
+    }
+
+    if (!metricsExists) {
+      const metricsTx = await routerProgram.methods
+        .initializeRouterMetrics()
+        .accounts({
+          routerState: routerStatePda,
+          routerMetrics: routerMetricsPda,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      console.log(" Router metrics initialized!");
+      console.log("Transaction signature:", metricsTx);
+    }
+
     // ========== INITIALIZE VAULT ==========
     console.log("\n Initializing Vault...");
 
-    // Find the vault PDA
+    // Find the WSOL vault PDA
     const [vaultPda] = await PublicKey.findProgramAddress(
-      [Buffer.from("vault")],
+      [Buffer.from("vault"), NATIVE_MINT.toBuffer()],
+      vaultProgram.programId
+    );
+
+    const [vaultTokenPda] = await PublicKey.findProgramAddress(
+      [Buffer.from("vault_token"), vaultPda.toBuffer()],
+      vaultProgram.programId
+    );
+
+    const [shareMintPda] = await PublicKey.findProgramAddress(
+      [Buffer.from("share_mint"), vaultPda.toBuffer()],
       vaultProgram.programId
     );
 
     console.log("Vault PDA:", vaultPda.toString());
+    console.log("Vault Token PDA:", vaultTokenPda.toString());
 
     // Check if vault is already initialized
     let vaultExists = false;
@@ -83,8 +163,12 @@ async function initialize() {
         .initializeVault()
         .accounts({
           vault: vaultPda,
+          vaultToken: vaultTokenPda,
+          mint: NATIVE_MINT,
+          shareMint: shareMintPda,
           authority: provider.wallet.publicKey,
           swapRouter: routerProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
         .rpc();
@@ -109,11 +193,15 @@ async function initialize() {
     console.log("\n Vault State:");
     console.log("  Authority:", vaultAccount.authority.toString());
     console.log("  Swap Router:", vaultAccount.swapRouter.toString());
+    console.log("  Vault Token:", vaultAccount.vaultToken.toString());
+    console.log("  Mint:", vaultAccount.mint.toString());
+    console.log("  Share Mint:", vaultAccount.shareMint.toString());
     console.log("  Total Shares:", vaultAccount.totalShares.toString());
 
     console.log("\n AtomX initialization completed successfully!");
     console.log("\nKey Addresses:");
     console.log("  Router State:", routerStatePda.toString());
+    console.log("  Router Metrics:", routerMetricsPda.toString());
     console.log("  Vault:", vaultPda.toString());
     console.log("  Router Program:", routerProgram.programId.toString());
     console.log("  Vault Program:", vaultProgram.programId.toString());
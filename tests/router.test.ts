@@ -61,6 +61,173 @@ describe("Jupiter Swap Router Tests", () => {
     }
   });
 
+  it("Migrates a v0 router state to v1", async () => {
+    console.log("\n📦 Test: Router State Migration");
+
+    // v0 layout: discriminator (8) + authority (32) + fee_rate_bps (2) + total_swaps (8) + total_volume (8) + bump (1)
+    // The v0 decode itself is covered by the router's unit tests; this only runs it on-chain
+    // when the cluster still holds a v0 account
+    const V0_LEN = 59;
+    const before = await provider.connection.getAccountInfo(routerState);
+    assert.ok(before, "router state should exist");
+
+    if (before.data.length === V0_LEN) {
+      const authority = new anchor.web3.PublicKey(before.data.subarray(8, 40));
+      const feeRateBps = before.data.readUInt16LE(40);
+      const totalSwaps = before.data.readBigUInt64LE(42);
+      const totalVolume = before.data.readBigUInt64LE(50);
+
+      await swapRouterProgram.methods
+        .migrateRouterState()
+        .accounts({
+          routerState: routerState,
+          payer: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      const routerAccount = await swapRouterProgram.account.routerState.fetch(routerState);
+      assert.equal(routerAccount.version, 1);
+      assert.equal(routerAccount.authority.toString(), authority.toString());
+      assert.equal(routerAccount.feeRateBps, feeRateBps);
+      assert.equal(routerAccount.totalSwaps.toString(), totalSwaps.toString());
+      assert.equal(routerAccount.totalVolume.toString(), totalVolume.toString());
+      assert.isFalse(routerAccount.paused);
+      assert.ok(routerAccount.pendingAuthority.equals(anchor.web3.PublicKey.default));
+      assert.ok(routerAccount.treasury.equals(anchor.web3.PublicKey.default));
+      console.log("✅ v0 router state migrated to v1");
+    } else {
+      const routerAccount = await swapRouterProgram.account.routerState.fetch(routerState);
+      assert.equal(routerAccount.version, 1);
+
+      // Current-layout accounts cannot be migrated again
+      try {
+        await swapRouterProgram.methods
+          .migrateRouterState()
+          .accounts({
+            routerState: routerState,
+            payer: provider.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .rpc();
+
+        assert.fail("Should have thrown an error");
+      } catch (error: any) {
+        assert.equal(error.error.errorCode.code, "InvalidRouterStateVersion");
+      }
+      console.log("✅ v1 router state rejected by migration");
+    }
+  });
+
+  it("Authority can update fee rate and pause", async () => {
+    console.log("\n🛠️  Test: Router Admin Instructions");
+
+    await swapRouterProgram.methods
+      .setFeeRate(25)
+      .accounts({
+        routerState: routerState,
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    let routerAccount = await swapRouterProgram.account.routerState.fetch(routerState);
+    assert.equal(routerAccount.feeRateBps, 25);
+
+    await swapRouterProgram.methods
+      .setPaused(true)
+      .accounts({
+        routerState: routerState,
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    routerAccount = await swapRouterProgram.account.routerState.fetch(routerState);
+    assert.isTrue(routerAccount.paused);
+
+    // Restore defaults for the remaining tests
+    await swapRouterProgram.methods
+      .setPaused(false)
+      .accounts({
+        routerState: routerState,
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+    await swapRouterProgram.methods
+      .setFeeRate(30)
+      .accounts({
+        routerState: routerState,
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    console.log("✅ Fee rate and pause flag updated by authority");
+  });
+
+  it("Rejects admin calls from non-authority", async () => {
+    console.log("\n🔒 Test: Router Admin Authorization");
+
+    const attacker = anchor.web3.Keypair.generate();
+
+    try {
+      await swapRouterProgram.methods
+        .setPaused(true)
+        .accounts({
+          routerState: routerState,
+          authority: attacker.publicKey,
+        })
+        .signers([attacker])
+        .rpc();
+
+      assert.fail("Should have thrown an error");
+    } catch (error: any) {
+      console.log("✅ Correctly failed with error:", error.error.errorMessage);
+      assert.equal(error.error.errorCode.code, "Unauthorized");
+    }
+  });
+
+  it("Returns rolling stats windows", async () => {
+    console.log("\n📊 Test: Router Stats Windows");
+
+    const [routerMetrics] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("router_metrics")],
+      swapRouterProgram.programId
+    );
+
+    try {
+      await swapRouterProgram.methods
+        .initializeRouterMetrics()
+        .accounts({
+          routerState: routerState,
+          routerMetrics: routerMetrics,
+          authority: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    } catch (error) {
+      console.log("ℹ️  Router metrics already initialized:", error.message);
+    }
+
+    for (const hours of [1, 24, 168]) {
+      const window = await swapRouterProgram.methods
+        .getStatsWindow(hours)
+        .accounts({ routerMetrics: routerMetrics })
+        .view();
+      assert.equal(window.hours, hours);
+      console.log(`✅ ${hours}h: ${window.swapCount.toString()} swaps, volume ${window.volume.toString()}`);
+    }
+
+    try {
+      await swapRouterProgram.methods
+        .getStatsWindow(169)
+        .accounts({ routerMetrics: routerMetrics })
+        .view();
+
+      assert.fail("Should have thrown an error");
+    } catch (error: any) {
+      assert.include(error.message, "InvalidStatsWindow");
+    }
+  });
+
   it("Can get Jupiter quote (mock)", async () => {
     console.log("\n💱 Test: Get Jupiter Quote");
     
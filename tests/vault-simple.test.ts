@@ -5,6 +5,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { Vault } from "../target/types/vault";
 import { SwapRouter } from "../target/types/swap_router";
+import { TOKEN_PROGRAM_ID, createMint } from "@solana/spl-token";
 import { assert } from "chai";
 
 describe("Vault Simple Tests", () => {
@@ -15,13 +16,34 @@ describe("Vault Simple Tests", () => {
   const swapRouterProgram = anchor.workspace.SwapRouter as Program<SwapRouter>;
 
   let vaultPda: anchor.web3.PublicKey;
+  let vaultTokenPda: anchor.web3.PublicKey;
+  let shareMintPda: anchor.web3.PublicKey;
+  let mint: anchor.web3.PublicKey;
 
   before(async () => {
     console.log("🔧 Setting up test environment...");
 
-    // Calculate vault PDA
+    mint = await createMint(
+      provider.connection,
+      provider.wallet.payer,
+      provider.wallet.publicKey,
+      null,
+      6
+    );
+
+    // Calculate vault PDA for the mint
     [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("vault")],
+      [Buffer.from("vault"), mint.toBuffer()],
+      vaultProgram.programId
+    );
+
+    [vaultTokenPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault_token"), vaultPda.toBuffer()],
+      vaultProgram.programId
+    );
+
+    [shareMintPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("share_mint"), vaultPda.toBuffer()],
       vaultProgram.programId
     );
 
@@ -37,8 +59,12 @@ describe("Vault Simple Tests", () => {
         .initializeVault()
         .accounts({
           vault: vaultPda,
+          vaultToken: vaultTokenPda,
+          mint: mint,
+          shareMint: shareMintPda,
           authority: provider.wallet.publicKey,
           swapRouter: swapRouterProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: anchor.web3.SystemProgram.programId,
         })
         .rpc();
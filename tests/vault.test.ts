@@ -12,7 +12,8 @@ import {
   mintTo,
   getAccount,
   getAssociatedTokenAddress,
-  createAssociatedTokenAccount
+  createAssociatedTokenAccount,
+  transfer
 } from "@solana/spl-token";
 import { assert } from "chai";
 
@@ -27,7 +28,13 @@ describe("Vault Tests", () => {
   let vaultTokenAccount: anchor.web3.PublicKey;
   let userTokenAccount: anchor.web3.PublicKey;
   let vaultPda: anchor.web3.PublicKey;
-  let userPositionPda: anchor.web3.PublicKey;
+  let shareMintPda: anchor.web3.PublicKey;
+  let userShareAccount: anchor.web3.PublicKey;
+
+  // Matches VIRTUAL_SHARES / VIRTUAL_ASSETS / LOCKED_SHARES in programs/vault/src/utils.rs
+  const VIRTUAL_SHARES = BigInt(1);
+  const VIRTUAL_ASSETS = BigInt(1);
+  const LOCKED_SHARES = BigInt(1000);
 
   before(async () => {
     console.log("🔧 Setting up test environment...");
@@ -44,7 +51,7 @@ describe("Vault Tests", () => {
 
     // Calculer le vault PDA d'abord
     [vaultPda] = await anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("vault")],
+      [Buffer.from("vault"), mint.toBuffer()],
       vaultProgram.programId
     );
 
@@ -60,17 +67,12 @@ describe("Vault Tests", () => {
     );
     console.log(" User token account created:", userTokenAccount.toString());
     
-    // Create vault's token account (also owned by user for simplicity)
-    vaultTokenAccount = await createAccount(
-      provider.connection,
-      provider.wallet.payer,
-      mint,
-      provider.wallet.publicKey,
-      undefined,
-      undefined,
-      TOKEN_PROGRAM_ID
+    // Vault token account is a PDA created and owned by the vault on initialization
+    [vaultTokenAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault_token"), vaultPda.toBuffer()],
+      vaultProgram.programId
     );
-    console.log(" Vault token account created:", vaultTokenAccount.toString());
+    console.log(" Vault token account PDA:", vaultTokenAccount.toString());
 
     // Mint 1000 tokens à l'user pour tester
     await mintTo(
@@ -83,9 +85,9 @@ describe("Vault Tests", () => {
     );
     console.log(" Minted 1000 tokens to user");
 
-    // Calculer le user position PDA
-    [userPositionPda] = await anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("position"), provider.wallet.publicKey.toBuffer()],
+    // Share mint PDA, created by initialize_vault
+    [shareMintPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("share_mint"), vaultPda.toBuffer()],
       vaultProgram.programId
     );
 
@@ -99,8 +101,12 @@ describe("Vault Tests", () => {
       .initializeVault()
       .accounts({
         vault: vaultPda,
+        vaultToken: vaultTokenAccount,
+        mint: mint,
+        shareMint: shareMintPda,
         authority: provider.wallet.publicKey,
         swapRouter: swapRouterProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .rpc();
@@ -112,7 +118,22 @@ describe("Vault Tests", () => {
     
     assert.ok(vaultAccount.authority.equals(provider.wallet.publicKey));
     assert.ok(vaultAccount.swapRouter.equals(swapRouterProgram.programId));
+    assert.ok(vaultAccount.vaultToken.equals(vaultTokenAccount));
+    assert.ok(vaultAccount.mint.equals(mint));
     assert.equal(vaultAccount.totalShares.toNumber(), 0);
+
+    assert.ok(vaultAccount.shareMint.equals(shareMintPda));
+
+    const vaultToken = await getAccount(provider.connection, vaultTokenAccount);
+    assert.ok(vaultToken.owner.equals(vaultPda));
+
+    // User's share token account
+    userShareAccount = await createAssociatedTokenAccount(
+      provider.connection,
+      provider.wallet.payer,
+      shareMintPda,
+      provider.wallet.publicKey
+    );
     
     console.log(" Vault initialized successfully");
     console.log("   Authority:", vaultAccount.authority.toString());
@@ -133,12 +154,13 @@ describe("Vault Tests", () => {
       .deposit(depositAmount)
       .accounts({
         vault: vaultPda,
-        userPosition: userPositionPda,
         user: provider.wallet.publicKey,
+        shareMint: shareMintPda,
+        userShareToken: userShareAccount,
         userToken: userTokenAccount,
         vaultToken: vaultTokenAccount,
+        mint: mint,
         tokenProgram: TOKEN_PROGRAM_ID,
-        systemProgram: anchor.web3.SystemProgram.programId,
       })
       .rpc();
 
@@ -157,18 +179,19 @@ describe("Vault Tests", () => {
     );
     assert.equal(vaultBalance.amount.toString(), depositAmount.toString());
 
-    // Vérifier la position de l'user
-    const userPosition = await vaultProgram.account.userPosition.fetch(userPositionPda);
-    console.log("User shares:", userPosition.shares.toNumber());
+    // Vérifier les shares de l'user
+    const userShares = await getAccount(provider.connection, userShareAccount);
+    console.log("User shares:", userShares.amount.toString());
     
-    assert.equal(userPosition.shares.toNumber(), depositAmount.toNumber());
-    assert.ok(userPosition.owner.equals(provider.wallet.publicKey));
+    // The first deposit mints one share per unit and leaves LOCKED_SHARES in the vault
+    const expectedShares = BigInt(depositAmount.toString());
+    assert.equal(userShares.amount.toString(), (expectedShares - LOCKED_SHARES).toString());
 
     // Vérifier le vault
     const vault = await vaultProgram.account.vault.fetch(vaultPda);
-    console.log("Total shares in vault:", vault.totalShares.toNumber());
+    console.log("Total shares in vault:", vault.totalShares.toString());
     
-    assert.equal(vault.totalShares.toNumber(), depositAmount.toNumber());
+    assert.equal(vault.totalShares.toString(), expectedShares.toString());
 
     console.log(" Deposit successful");
   });
@@ -180,44 +203,242 @@ describe("Vault Tests", () => {
 
     // État avant
     const vaultBefore = await vaultProgram.account.vault.fetch(vaultPda);
-    const userPositionBefore = await vaultProgram.account.userPosition.fetch(userPositionPda);
+    const userSharesBefore = await getAccount(provider.connection, userShareAccount);
     
-    console.log("Shares before:", userPositionBefore.shares.toNumber());
+    console.log("Shares before:", userSharesBefore.amount.toString());
     console.log("Total shares before:", vaultBefore.totalShares.toNumber());
 
     const tx = await vaultProgram.methods
       .deposit(depositAmount)
       .accounts({
         vault: vaultPda,
-        userPosition: userPositionPda,
         user: provider.wallet.publicKey,
+        shareMint: shareMintPda,
+        userShareToken: userShareAccount,
         userToken: userTokenAccount,
         vaultToken: vaultTokenAccount,
+        mint: mint,
         tokenProgram: TOKEN_PROGRAM_ID,
-        systemProgram: anchor.web3.SystemProgram.programId,
       })
       .rpc();
 
     console.log("Transaction signature:", tx);
 
     // État après
-    const userPositionAfter = await vaultProgram.account.userPosition.fetch(userPositionPda);
+    const userSharesAfter = await getAccount(provider.connection, userShareAccount);
     const vaultAfter = await vaultProgram.account.vault.fetch(vaultPda);
     
-    console.log("Shares after:", userPositionAfter.shares.toNumber());
+    console.log("Shares after:", userSharesAfter.amount.toString());
     console.log("Total shares after:", vaultAfter.totalShares.toNumber());
 
     // Les shares augmentent
-    assert.ok(userPositionAfter.shares.gt(userPositionBefore.shares));
+    assert.ok(userSharesAfter.amount > userSharesBefore.amount);
 
     console.log(" Second deposit successful");
   });
 
+  it("Prices deposits after a donation to the vault token account", async () => {
+    console.log("\n Test: Deposit after a donation");
+
+    // Donate straight to the vault token account, bypassing deposit
+    await transfer(
+      provider.connection,
+      provider.wallet.payer,
+      userTokenAccount,
+      vaultTokenAccount,
+      provider.wallet.publicKey,
+      200_000000
+    );
+
+    const depositAmount = BigInt(10_000000);
+    const vaultBefore = await vaultProgram.account.vault.fetch(vaultPda);
+    const totalShares = BigInt(vaultBefore.totalShares.toString());
+    const totalAssets = (await getAccount(provider.connection, vaultTokenAccount)).amount;
+    const sharesBefore = (await getAccount(provider.connection, userShareAccount)).amount;
+
+    await vaultProgram.methods
+      .deposit(new anchor.BN(depositAmount.toString()))
+      .accounts({
+        vault: vaultPda,
+        user: provider.wallet.publicKey,
+        shareMint: shareMintPda,
+        userShareToken: userShareAccount,
+        userToken: userTokenAccount,
+        vaultToken: vaultTokenAccount,
+        mint: mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const minted = (await getAccount(provider.connection, userShareAccount)).amount - sharesBefore;
+    const expected = depositAmount * (totalShares + VIRTUAL_SHARES) / (totalAssets + VIRTUAL_ASSETS);
+    console.log("Shares minted after donation:", minted.toString());
+    assert.equal(minted.toString(), expected.toString());
+
+    // The donation goes to existing holders; the depositor loses at most one share's worth of rounding
+    const redeemable = minted * (totalAssets + depositAmount + VIRTUAL_ASSETS)
+      / (totalShares + minted + VIRTUAL_SHARES);
+    const sharePrice = (totalAssets + VIRTUAL_ASSETS) / (totalShares + VIRTUAL_SHARES) + BigInt(1);
+    assert.ok(depositAmount - redeemable <= sharePrice);
+
+    console.log(" Deposit priced on the donated balance");
+  });
+
+  it("Rejects deposits that would mint zero shares", async () => {
+    console.log("\n Test: Zero-share deposit (should fail)");
+
+    try {
+      await vaultProgram.methods
+        .deposit(new anchor.BN(0))
+        .accounts({
+          vault: vaultPda,
+          user: provider.wallet.publicKey,
+          shareMint: shareMintPda,
+          userShareToken: userShareAccount,
+          userToken: userTokenAccount,
+          vaultToken: vaultTokenAccount,
+          mint: mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      assert.fail("Should have thrown an error");
+    } catch (error: any) {
+      console.log(" Correctly failed with error:", error.error.errorMessage);
+      assert.equal(error.error.errorCode.code, "ZeroShares");
+    }
+  });
+
+  it("Only trips the circuit breaker on a completed vault swap", async () => {
+    console.log("\n Test: Circuit breaker and vault arbitrage");
+
+    const JUPITER_PROGRAM_ID = new anchor.web3.PublicKey("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+    const routerPda = (...seeds: Buffer[]) =>
+      anchor.web3.PublicKey.findProgramAddressSync(seeds, swapRouterProgram.programId)[0];
+    const routerState = routerPda(Buffer.from("router_state"));
+    const routerMetrics = routerPda(Buffer.from("router_metrics"));
+    const dexRegistry = routerPda(Buffer.from("dex_registry"));
+    const mintStats = routerPda(Buffer.from("mint_stats"), mint.toBuffer());
+
+    try {
+      await swapRouterProgram.methods
+        .initializeDexRegistry()
+        .accounts({
+          routerState,
+          dexRegistry,
+          authority: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    } catch (error) {
+      console.log("ℹ️  DEX registry already initialized:", error.message);
+    }
+    try {
+      await swapRouterProgram.methods
+        .initializeRouterMetrics()
+        .accounts({
+          routerState,
+          routerMetrics,
+          authority: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    } catch (error) {
+      console.log("ℹ️  Router metrics already initialized:", error.message);
+    }
+
+    // Any swap of more than one unit this hour crosses the cap
+    await swapRouterProgram.methods
+      .setCircuitBreaker(new anchor.BN(0), new anchor.BN(1))
+      .accounts({ routerState, authority: provider.wallet.publicKey })
+      .rpc();
+
+    try {
+      const vaultBalanceBefore = (await getAccount(provider.connection, vaultTokenAccount)).amount;
+      const amountIn = new anchor.BN(vaultBalanceBefore.toString());
+
+      // Jupiter `route` with an empty route plan: in_amount, quoted_out_amount, slippage_bps, platform_fee_bps
+      const routeData = Buffer.concat([
+        Buffer.from([229, 23, 203, 151, 122, 227, 173, 42]),
+        Buffer.alloc(4),
+        amountIn.toArrayLike(Buffer, "le", 8),
+        amountIn.addn(1).toArrayLike(Buffer, "le", 8),
+        Buffer.from([50, 0, 0]),
+      ]);
+      const readonly = (pubkey: anchor.web3.PublicKey) => ({ pubkey, isSigner: false, isWritable: false });
+      const executeArbitrage = () => vaultProgram.methods
+        .executeArbitrage(routeData, amountIn, new anchor.BN(1))
+        .accounts({
+          vault: vaultPda,
+          vaultToken: vaultTokenAccount,
+          executor: provider.wallet.publicKey,
+          executorToken: userTokenAccount,
+          mint: mint,
+          swapRouterProgram: swapRouterProgram.programId,
+          routerState,
+          routerMetrics,
+          mintStats,
+          dexRegistry,
+          jupiterProgram: JUPITER_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .remainingAccounts([
+          readonly(TOKEN_PROGRAM_ID),
+          readonly(vaultPda),
+          readonly(vaultTokenAccount),
+          readonly(vaultTokenAccount),
+          readonly(JUPITER_PROGRAM_ID),
+        ])
+        .rpc();
+
+      // The empty route swaps nothing, so the arbitrage fails and cannot leave the router paused
+      try {
+        await executeArbitrage();
+        assert.fail("Should have thrown an error");
+      } catch (error: any) {
+        console.log(" Correctly failed with error:", error.message);
+      }
+
+      const router = await swapRouterProgram.account.routerState.fetch(routerState);
+      assert.isFalse(router.paused);
+
+      const vaultBalanceAfter = (await getAccount(provider.connection, vaultTokenAccount)).amount;
+      assert.equal(vaultBalanceAfter.toString(), vaultBalanceBefore.toString());
+
+      // Once paused, arbitrage is rejected by the router
+      await swapRouterProgram.methods
+        .setPaused(true)
+        .accounts({ routerState, authority: provider.wallet.publicKey })
+        .rpc();
+      try {
+        await executeArbitrage();
+        assert.fail("Should have thrown an error");
+      } catch (error: any) {
+        assert.ok(
+          (error.logs ?? []).some((log: string) => log.includes("RouterPaused")),
+          error.message
+        );
+      }
+
+      console.log(" Router left unpaused by a failed arbitrage and rejects arbitrage while paused");
+    } finally {
+      await swapRouterProgram.methods
+        .setPaused(false)
+        .accounts({ routerState, authority: provider.wallet.publicKey })
+        .rpc();
+      await swapRouterProgram.methods
+        .setCircuitBreaker(new anchor.BN(0), new anchor.BN(0))
+        .accounts({ routerState, authority: provider.wallet.publicKey })
+        .rpc();
+    }
+  });
+
   it("Withdraw from Vault", async () => {
     console.log("\n Test: Withdraw from Vault");
 
-    const userPosition = await vaultProgram.account.userPosition.fetch(userPositionPda);
-    const sharesToWithdraw = userPosition.shares.div(new anchor.BN(2)); // Retire 50%
+    const userShares = await getAccount(provider.connection, userShareAccount);
+    const sharesToWithdraw = new anchor.BN((userShares.amount / BigInt(2)).toString()); // Retire 50%
 
     console.log("Withdrawing shares:", sharesToWithdraw.toNumber());
 
@@ -229,12 +450,13 @@ describe("Vault Tests", () => {
       .withdraw(sharesToWithdraw)
       .accounts({
         vault: vaultPda,
-        userPosition: userPositionPda,
         user: provider.wallet.publicKey,
+        shareMint: shareMintPda,
+        userShareToken: userShareAccount,
         vaultToken: vaultTokenAccount,
         userToken: userTokenAccount,
+        mint: mint,
         tokenProgram: TOKEN_PROGRAM_ID,
-        owner: provider.wallet.publicKey,
       })
       .rpc();
 
@@ -247,32 +469,83 @@ describe("Vault Tests", () => {
     // L'user a reçu des tokens
     assert.ok(BigInt(userBalanceAfter.amount.toString()) > BigInt(userBalanceBefore.amount.toString()));
 
-    // Vérifier la position
-    const userPositionAfter = await vaultProgram.account.userPosition.fetch(userPositionPda);
-    console.log("Shares remaining:", userPositionAfter.shares.toNumber());
+    // Les share tokens sont brûlés
+    const userSharesAfter = await getAccount(provider.connection, userShareAccount);
+    console.log("Shares remaining:", userSharesAfter.amount.toString());
     
-    assert.ok(userPositionAfter.shares.lt(userPosition.shares));
+    assert.ok(userSharesAfter.amount < userShares.amount);
 
     console.log(" Withdrawal successful");
   });
 
+  it("Rejects a token account that is not the vault's", async () => {
+    console.log("\n Test: Deposit into a foreign token account (should fail)");
+
+    try {
+      await vaultProgram.methods
+        .deposit(new anchor.BN(1_000000))
+        .accounts({
+          vault: vaultPda,
+          user: provider.wallet.publicKey,
+          shareMint: shareMintPda,
+          userShareToken: userShareAccount,
+          userToken: userTokenAccount,
+          vaultToken: userTokenAccount,
+          mint: mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      assert.fail("Should have thrown an error");
+    } catch (error: any) {
+      console.log(" Correctly failed with error:", error.error.errorMessage);
+      assert.equal(error.error.errorCode.code, "InvalidVaultToken");
+    }
+  });
+
+  it("Rejects withdrawals of zero shares", async () => {
+    console.log("\n Test: Zero-share withdrawal (should fail)");
+
+    try {
+      await vaultProgram.methods
+        .withdraw(new anchor.BN(0))
+        .accounts({
+          vault: vaultPda,
+          user: provider.wallet.publicKey,
+          shareMint: shareMintPda,
+          userShareToken: userShareAccount,
+          vaultToken: vaultTokenAccount,
+          userToken: userTokenAccount,
+          mint: mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      assert.fail("Should have thrown an error");
+    } catch (error: any) {
+      console.log(" Correctly failed with error:", error.error.errorMessage);
+      assert.equal(error.error.errorCode.code, "ZeroWithdrawal");
+    }
+  });
+
   it("Cannot withdraw more shares than owned", async () => {
     console.log("\n Test: Withdraw too many shares (should fail)");
 
-    const userPosition = await vaultProgram.account.userPosition.fetch(userPositionPda);
-    const tooManyShares = userPosition.shares.add(new anchor.BN(1000000));
+    const userShares = await getAccount(provider.connection, userShareAccount);
+    const tooManyShares = new anchor.BN(userShares.amount.toString()).add(new anchor.BN(1000000));
 
     try {
       await vaultProgram.methods
         .withdraw(tooManyShares)
         .accounts({
           vault: vaultPda,
-          userPosition: userPositionPda,
           user: provider.wallet.publicKey,
+          shareMint: shareMintPda,
+          userShareToken: userShareAccount,
           vaultToken: vaultTokenAccount,
           userToken: userTokenAccount,
+          mint: mint,
           tokenProgram: TOKEN_PROGRAM_ID,
-          owner: provider.wallet.publicKey,
         })
         .rpc();
 